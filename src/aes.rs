@@ -0,0 +1,262 @@
+// AES (FIPS-197) block encryption and decryption for 128, 192 and 256-bit keys.
+// This is a straightforward, byte-oriented implementation that follows the spec
+// step by step rather than the fast table-driven version, which makes it easy
+// to check against the pseudocode but also means it is NOT constant time. That's
+// fine for attacking cryptopals oracles; don't protect anything real with it.
+//
+// The state is kept as a flat 16-byte block in the same order as the input,
+// which is column-major: byte r + 4c is row r of column c.
+
+/// the size of an AES block in bytes, regardless of key size
+pub const BLOCK_SIZE: usize = 16;
+
+/// the substitution box used by SubBytes
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// the inverse of `SBOX`, used by InvSubBytes
+const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+/// round constants for the key expansion (only the first byte of each word is
+/// non-zero, so that's all we store)
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// an AES key, expanded into its round keys
+#[derive(Clone)]
+pub struct Aes {
+    round_keys: Vec<[u8; 16]>,
+}
+
+/// multiplies by x (i.e. 2) in GF(2^8)
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ (if b & 0x80 != 0 { 0x1b } else { 0 })
+}
+
+/// multiplies two elements of GF(2^8)
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            p ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    p
+}
+
+fn sub_bytes(state: &mut [u8]) {
+    for b in state.iter_mut() {
+        *b = SBOX[*b as usize];
+    }
+}
+
+fn inv_sub_bytes(state: &mut [u8]) {
+    for b in state.iter_mut() {
+        *b = INV_SBOX[*b as usize];
+    }
+}
+
+/// rotates row r of the state left by r places
+fn shift_rows(state: &mut [u8]) {
+    let old = state.to_vec();
+    for r in 1..4 {
+        for c in 0..4 {
+            state[r + 4*c] = old[r + 4*((c + r) % 4)];
+        }
+    }
+}
+
+/// rotates row r of the state right by r places
+fn inv_shift_rows(state: &mut [u8]) {
+    let old = state.to_vec();
+    for r in 1..4 {
+        for c in 0..4 {
+            state[r + 4*((c + r) % 4)] = old[r + 4*c];
+        }
+    }
+}
+
+/// multiplies each column by the fixed polynomial whose coefficient for x^i is
+/// coeffs[i] (mod x^4 + 1)
+fn mix_columns_with(state: &mut [u8], coeffs: [u8; 4]) {
+    for c in 0..4 {
+        let col = [state[4*c], state[4*c + 1], state[4*c + 2], state[4*c + 3]];
+        for r in 0..4 {
+            state[4*c + r] = gmul(col[0], coeffs[r]) ^
+                             gmul(col[1], coeffs[(r + 3) % 4]) ^
+                             gmul(col[2], coeffs[(r + 2) % 4]) ^
+                             gmul(col[3], coeffs[(r + 1) % 4]);
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8]) {
+    mix_columns_with(state, [0x02, 0x01, 0x01, 0x03]);
+}
+
+fn inv_mix_columns(state: &mut [u8]) {
+    mix_columns_with(state, [0x0e, 0x09, 0x0d, 0x0b]);
+}
+
+fn add_round_key(state: &mut [u8], round_key: &[u8; 16]) {
+    for (b, k) in state.iter_mut().zip(round_key.iter()) {
+        *b ^= *k;
+    }
+}
+
+impl Aes {
+    /// expands a 16, 24 or 32-byte key (AES-128, AES-192 or AES-256)
+    /// panics on any other key length
+    pub fn new(key: &[u8]) -> Aes {
+        let nk = key.len() / 4;
+        assert!(key.len() == 16 || key.len() == 24 || key.len() == 32,
+                "AES keys must be 16, 24 or 32 bytes, not {}", key.len());
+        let rounds = nk + 6;
+
+        // the key schedule is defined in terms of 4-byte words
+        let total_words = 4 * (rounds + 1);
+        let mut w: Vec<[u8; 4]> = Vec::with_capacity(total_words);
+        for i in 0..nk {
+            w.push([key[4*i], key[4*i + 1], key[4*i + 2], key[4*i + 3]]);
+        }
+        for i in nk..total_words {
+            let mut temp = w[i - 1];
+            if i % nk == 0 {
+                // RotWord, SubWord, then xor in the round constant
+                temp = [SBOX[temp[1] as usize] ^ RCON[i/nk - 1],
+                        SBOX[temp[2] as usize],
+                        SBOX[temp[3] as usize],
+                        SBOX[temp[0] as usize]];
+            } else if nk > 6 && i % nk == 4 {
+                for b in temp.iter_mut() {
+                    *b = SBOX[*b as usize];
+                }
+            }
+            let prev = w[i - nk];
+            w.push([prev[0] ^ temp[0], prev[1] ^ temp[1], prev[2] ^ temp[2], prev[3] ^ temp[3]]);
+        }
+
+        let round_keys = w.chunks(4).map(|words| {
+            let mut k = [0u8; 16];
+            for (i, word) in words.iter().enumerate() {
+                k[4*i..4*i + 4].copy_from_slice(word);
+            }
+            k
+        }).collect();
+        Aes { round_keys: round_keys }
+    }
+
+    /// the number of rounds (10, 12 or 14 depending on the key size)
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    /// encrypts a single 16-byte block in place
+    pub fn encrypt_block(&self, block: &mut [u8]) {
+        assert_eq!(block.len(), BLOCK_SIZE);
+        let rounds = self.rounds();
+        add_round_key(block, &self.round_keys[0]);
+        for round in 1..rounds {
+            sub_bytes(block);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.round_keys[round]);
+        }
+        sub_bytes(block);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[rounds]);
+    }
+
+    /// decrypts a single 16-byte block in place
+    pub fn decrypt_block(&self, block: &mut [u8]) {
+        assert_eq!(block.len(), BLOCK_SIZE);
+        let rounds = self.rounds();
+        add_round_key(block, &self.round_keys[rounds]);
+        for round in (1..rounds).rev() {
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+            add_round_key(block, &self.round_keys[round]);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        inv_sub_bytes(block);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cryptoutil::{bytes_to_hex, hex_to_bytes};
+
+    /// checks that <key> encrypts <plaintext> to <ciphertext> and back (all hex)
+    fn check(key: &str, plaintext: &str, ciphertext: &str, rounds: usize) {
+        let aes = Aes::new(&hex_to_bytes(key));
+        assert_eq!(aes.rounds(), rounds);
+        let mut block = hex_to_bytes(plaintext);
+        aes.encrypt_block(&mut block);
+        assert_eq!(bytes_to_hex(&block), ciphertext);
+        aes.decrypt_block(&mut block);
+        assert_eq!(bytes_to_hex(&block), plaintext);
+    }
+
+    // FIPS-197 appendix B
+    #[test]
+    fn cipher_example() {
+        check("2b7e151628aed2a6abf7158809cf4f3c", "3243f6a8885a308d313198a2e0370734",
+              "3925841d02dc09fbdc118597196a0b32", 10);
+    }
+
+    // FIPS-197 appendix C.1-C.3
+    #[test]
+    fn aes_128() {
+        check("000102030405060708090a0b0c0d0e0f", "00112233445566778899aabbccddeeff",
+              "69c4e0d86a7b0430d8cdb78070b4c55a", 10);
+    }
+
+    #[test]
+    fn aes_192() {
+        check("000102030405060708090a0b0c0d0e0f1011121314151617", "00112233445566778899aabbccddeeff",
+              "dda97ca4864cdfe06eaf70a0ec0d7191", 12);
+    }
+
+    #[test]
+    fn aes_256() {
+        check("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+              "00112233445566778899aabbccddeeff", "8ea2b7ca516745bfeafc49904b496089", 14);
+    }
+}
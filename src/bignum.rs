@@ -0,0 +1,521 @@
+// Arbitrary-precision unsigned integers, just enough for public-key crypto:
+// addition, subtraction, multiplication, division with remainder, modular
+// exponentiation and modular inverses.
+// Numbers are stored as little-endian 32-bit limbs with no leading zero limbs, so
+// every number has exactly one representation (zero is no limbs at all), and a
+// 64-bit product or quotient of two limbs never overflows. Division is Knuth's
+// algorithm D, which is what keeps `modpow` on 1536-bit and bigger numbers
+// quick.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+use cryptoutil;
+use rng::RandomSource;
+
+const LIMB_BITS: usize = 32;
+const BASE: u64 = 1 << LIMB_BITS;
+
+/// an arbitrary-precision unsigned integer
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// least significant limb first, with no zero limbs on the end
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// builds a number from limbs, dropping leading zeroes
+    fn from_limbs(mut limbs: Vec<u32>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs: limbs }
+    }
+
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from_u64(1)
+    }
+
+    pub fn from_u64(n: u64) -> BigUint {
+        BigUint::from_limbs(vec![n as u32, (n >> LIMB_BITS) as u32])
+    }
+
+    /// returns the number as a u64, or None if it's too big for one
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some((self.limbs[1] as u64) << LIMB_BITS | self.limbs[0] as u64),
+            _ => None,
+        }
+    }
+
+    /// reads a big-endian byte string, which may have leading zeroes
+    pub fn from_bytes_be(bytes: &[u8]) -> BigUint {
+        let limbs = bytes.rchunks(4)
+                         .map(|chunk| chunk.iter().fold(0, |acc, &b| (acc << 8) | b as u32))
+                         .collect();
+        BigUint::from_limbs(limbs)
+    }
+
+    /// returns the number as a big-endian byte string with no leading zeroes
+    /// (so zero is the empty string)
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.limbs.iter().rev()
+                                     .flat_map(|limb| (0..4).rev().map(move |i| (limb >> (8 * i)) as u8))
+                                     .collect();
+        let leading = bytes.iter().take_while(|&&b| b == 0).count();
+        bytes.drain(..leading);
+        bytes
+    }
+
+    /// returns the number as a big-endian byte string of exactly <len> bytes,
+    /// padded with leading zeroes
+    /// panics if the number doesn't fit
+    pub fn to_bytes_be_padded(&self, len: usize) -> Vec<u8> {
+        let bytes = self.to_bytes_be();
+        assert!(bytes.len() <= len, "{} bytes don't fit in {}", bytes.len(), len);
+        let mut padded = vec![0; len - bytes.len()];
+        padded.extend(bytes);
+        padded
+    }
+
+    /// parses a hex string (of either case, odd lengths allowed)
+    /// panics on anything that isn't hex, like `cryptoutil::hex_to_bytes`
+    pub fn from_hex(s: &str) -> BigUint {
+        if s.len() % 2 == 1 {
+            BigUint::from_bytes_be(&cryptoutil::hex_to_bytes(&format!("0{}", s)))
+        } else {
+            BigUint::from_bytes_be(&cryptoutil::hex_to_bytes(s))
+        }
+    }
+
+    /// returns the number in lowercase hex with no leading zeroes ("0" for zero)
+    pub fn to_hex(&self) -> String {
+        format!("{:x}", self)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.limbs.first().map_or(true, |limb| limb & 1 == 0)
+    }
+
+    /// the number of bits needed to write the number down (0 for zero)
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * LIMB_BITS - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// returns bit <i>, counting from the least significant
+    pub fn bit(&self, i: usize) -> bool {
+        self.limbs.get(i / LIMB_BITS).map_or(false, |limb| (limb >> (i % LIMB_BITS)) & 1 == 1)
+    }
+
+    /// returns a uniformly random number in [0, <bound>)
+    pub fn random_below<R: RandomSource>(bound: &BigUint, rng: &mut R) -> BigUint {
+        assert!(!bound.is_zero(), "no numbers below zero");
+        let bits = bound.bits();
+        let len = (bits + 7) / 8;
+        // generate numbers with as many bits as the bound until one is below it,
+        // which takes at most two tries on average
+        loop {
+            let mut bytes = rng.gen_bytes(len);
+            bytes[0] &= 0xff >> (len * 8 - bits);
+            let n = BigUint::from_bytes_be(&bytes);
+            if &n < bound {
+                return n;
+            }
+        }
+    }
+
+    fn add_limbs(&self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() { (self, other) } else { (other, self) };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0;
+        for (i, &a) in long.limbs.iter().enumerate() {
+            let sum = a as u64 + *short.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> LIMB_BITS;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+
+    /// returns self - <other>, or None if that would be negative
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let diff = a as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            limbs.push(diff as u32);
+            borrow = if diff < 0 { 1 } else { 0 };
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    fn mul_limbs(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                // a * b + limb + carry is at most (2^32 - 1)^2 + 2 * (2^32 - 1),
+                // which is exactly 2^64 - 1
+                let t = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = t as u32;
+                carry = t >> LIMB_BITS;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    /// divides by a single limb, returning the quotient and remainder
+    fn divmod_limb(&self, d: u32) -> (BigUint, u32) {
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut rem = 0u64;
+        for i in (0..self.limbs.len()).rev() {
+            let cur = (rem << LIMB_BITS) | self.limbs[i] as u64;
+            quotient[i] = (cur / d as u64) as u32;
+            rem = cur % d as u64;
+        }
+        (BigUint::from_limbs(quotient), rem as u32)
+    }
+
+    /// returns the quotient and remainder of dividing by <divisor>
+    /// panics if <divisor> is zero
+    pub fn divmod(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero(), "division by zero");
+        if *self < *divisor {
+            return (BigUint::zero(), self.clone());
+        }
+        if divisor.limbs.len() == 1 {
+            let (q, r) = self.divmod_limb(divisor.limbs[0]);
+            return (q, BigUint::from_u64(r as u64));
+        }
+
+        // Knuth vol. 2, 4.3.1, algorithm D. Shift both numbers so the divisor's
+        // top limb has its high bit set; then the quotient digit guessed from the
+        // top two limbs of the remainder is at most 2 too big.
+        let shift = divisor.limbs.last().unwrap().leading_zeros() as usize;
+        let v = divisor.shl(shift).limbs;
+        let mut u = self.shl(shift).limbs;
+        u.push(0);
+        let n = v.len();
+        let m = u.len() - n - 1;
+        let mut quotient = vec![0u32; m + 1];
+        let (v_top, v_next) = (v[n - 1] as u64, v[n - 2] as u64);
+
+        for j in (0..m + 1).rev() {
+            let top = (u[j + n] as u64) << LIMB_BITS | u[j + n - 1] as u64;
+            let mut qhat = top / v_top;
+            let mut rhat = top % v_top;
+            while qhat >= BASE || qhat * v_next > (rhat << LIMB_BITS | u[j + n - 2] as u64) {
+                qhat -= 1;
+                rhat += v_top;
+                if rhat >= BASE {
+                    break;
+                }
+            }
+
+            // u[j..j+n+1] -= qhat * v
+            let mut borrow = 0i64;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let p = qhat * v[i] as u64 + carry;
+                carry = p >> LIMB_BITS;
+                let t = u[i + j] as i64 - borrow - (p & 0xffffffff) as i64;
+                u[i + j] = t as u32;
+                borrow = if t < 0 { 1 } else { 0 };
+            }
+            let t = u[j + n] as i64 - borrow - carry as i64;
+            u[j + n] = t as u32;
+
+            // qhat was still one too big (rare): add v back
+            if t < 0 {
+                qhat -= 1;
+                let mut carry = 0u64;
+                for i in 0..n {
+                    let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                    u[i + j] = sum as u32;
+                    carry = sum >> LIMB_BITS;
+                }
+                u[j + n] = u[j + n].wrapping_add(carry as u32);
+            }
+            quotient[j] = qhat as u32;
+        }
+
+        u.truncate(n);
+        (BigUint::from_limbs(quotient), BigUint::from_limbs(u).shr(shift))
+    }
+
+    /// shifts left by <bits> bits
+    pub fn shl(&self, bits: usize) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+        let (whole, part) = (bits / LIMB_BITS, bits % LIMB_BITS);
+        let mut limbs = vec![0; whole];
+        let mut carry = 0;
+        for &limb in &self.limbs {
+            let wide = (limb as u64) << part;
+            limbs.push(wide as u32 | carry);
+            carry = (wide >> LIMB_BITS) as u32;
+        }
+        limbs.push(carry);
+        BigUint::from_limbs(limbs)
+    }
+
+    /// shifts right by <bits> bits
+    pub fn shr(&self, bits: usize) -> BigUint {
+        let (whole, part) = (bits / LIMB_BITS, bits % LIMB_BITS);
+        if whole >= self.limbs.len() {
+            return BigUint::zero();
+        }
+        let high = &self.limbs[whole..];
+        let limbs = (0..high.len()).map(|i| {
+            let wide = (*high.get(i + 1).unwrap_or(&0) as u64) << LIMB_BITS | high[i] as u64;
+            (wide >> part) as u32
+        }).collect();
+        BigUint::from_limbs(limbs)
+    }
+
+    /// returns self^<exponent> mod <modulus>, by square-and-multiply
+    /// panics if <modulus> is zero
+    pub fn modpow(&self, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero(), "modulus is zero");
+        let base = self % modulus;
+        let mut result = &BigUint::one() % modulus;
+        for i in (0..exponent.bits()).rev() {
+            result = &(&result * &result) % modulus;
+            if exponent.bit(i) {
+                result = &(&result * &base) % modulus;
+            }
+        }
+        result
+    }
+
+    /// returns the x with self * x = 1 mod <modulus>, or None if there isn't one
+    /// (that is, if self and <modulus> aren't coprime)
+    pub fn modinv(&self, modulus: &BigUint) -> Option<BigUint> {
+        assert!(!modulus.is_zero(), "modulus is zero");
+        // extended Euclid, keeping only the coefficients of self, and keeping
+        // them reduced mod <modulus> so they never go negative
+        let (mut r0, mut r1) = (modulus.clone(), self % modulus);
+        let (mut t0, mut t1) = (BigUint::zero(), BigUint::one());
+        while !r1.is_zero() {
+            let (q, r) = r0.divmod(&r1);
+            let qt = &(&q * &t1) % modulus;
+            let t = &(&t0 + modulus) - &qt;
+            let t = &t % modulus;
+            r0 = r1;
+            r1 = r;
+            t0 = t1;
+            t1 = t;
+        }
+        if r0 == BigUint::one() {
+            Some(&t0 % modulus)
+        } else {
+            None
+        }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Add for &'a BigUint {
+    type Output = BigUint;
+    fn add(self, other: &BigUint) -> BigUint {
+        self.add_limbs(other)
+    }
+}
+
+impl<'a> Sub for &'a BigUint {
+    type Output = BigUint;
+    fn sub(self, other: &BigUint) -> BigUint {
+        // like the built-in integer types, panic instead of wrapping
+        self.checked_sub(other).expect("subtraction underflowed")
+    }
+}
+
+impl<'a> Mul for &'a BigUint {
+    type Output = BigUint;
+    fn mul(self, other: &BigUint) -> BigUint {
+        self.mul_limbs(other)
+    }
+}
+
+impl<'a> Div for &'a BigUint {
+    type Output = BigUint;
+    fn div(self, other: &BigUint) -> BigUint {
+        self.divmod(other).0
+    }
+}
+
+impl<'a> Rem for &'a BigUint {
+    type Output = BigUint;
+    fn rem(self, other: &BigUint) -> BigUint {
+        self.divmod(other).1
+    }
+}
+
+impl fmt::LowerHex for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.limbs.split_last() {
+            None => write!(f, "0"),
+            Some((top, rest)) => {
+                write!(f, "{:x}", top)?;
+                for limb in rest.iter().rev() {
+                    write!(f, "{:08x}", limb)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // peel off 9 decimal digits at a time
+        let mut chunks = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (q, r) = n.divmod_limb(1_000_000_000);
+            chunks.push(r);
+            n = q;
+        }
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((top, rest)) => {
+                write!(f, "{}", top)?;
+                for chunk in rest.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:x}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> BigUint {
+        BigUint::from_hex(s)
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(BigUint::zero().to_hex(), "0");
+        assert_eq!(BigUint::zero().to_bytes_be(), vec![]);
+        assert_eq!(hex("00abc").to_hex(), "abc");
+        assert_eq!(hex("0102030405").to_bytes_be(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(hex("0102").to_bytes_be_padded(4), vec![0, 0, 1, 2]);
+        assert_eq!(BigUint::from_u64(u64::max_value()).to_u64(), Some(u64::max_value()));
+        assert_eq!(hex("10000000000000000").to_u64(), None);
+        assert_eq!(hex("123456789abcdef0123").bits(), 73);
+        assert_eq!(format!("{}", BigUint::from_u64(1000000000)), "1000000000");
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = BigUint::from_u64(12345678901234567890).mul(&BigUint::from_u64(1000000000));
+        assert_eq!(format!("{}", a), "12345678901234567890000000000");
+        let b = &hex("ffffffffffffffff") + &BigUint::one();
+        assert_eq!(b.to_hex(), "10000000000000000");
+        assert_eq!((&b - &BigUint::one()).to_hex(), "ffffffffffffffff");
+        assert_eq!(BigUint::one().checked_sub(&b), None);
+        let c = &BigUint::from_u64(9876543210987654321) * &a;
+        assert_eq!(&(&c / &a) * &a, c);
+        assert_eq!(hex("1").shl(100).shr(99).to_hex(), "2");
+    }
+
+    #[test]
+    fn divmod_single_limb() {
+        let (q, r) = hex("123456789abcdef0123456789").divmod(&BigUint::from_u64(1000));
+        assert_eq!((q.to_hex(), r.to_u64()), ("4a90be587de6e51f02e2a9".to_string(), Some(865)));
+    }
+
+    // divisions that need the rarer steps of algorithm D (the examples from
+    // Hacker's Delight's divmnu64)
+    #[test]
+    fn divmod_add_back() {
+        let (q, r) = hex("7fffffff800000000000000000000000").divmod(&hex("800000000000000000000001"));
+        assert_eq!((q.to_hex(), r.to_hex()), ("fffffffe".to_string(), "7fffffffffffffff00000002".to_string()));
+        let (q, r) = hex("80000000000000000000000000000003").divmod(&hex("200000000000000000000001"));
+        assert_eq!((q.to_hex(), r.to_hex()), ("3ffffffff".to_string(), "1ffffffffffffffc00000004".to_string()));
+    }
+
+    #[test]
+    fn divmod_qhat_correction() {
+        let (q, r) = hex("8000000000000000fffffffe00000000").divmod(&hex("8000000000000000ffffffff"));
+        assert_eq!((q.to_hex(), r.to_hex()), ("ffffffff".to_string(), "7fffffffffffffffffffffff".to_string()));
+    }
+
+    #[test]
+    fn divmod_small_dividend() {
+        let (q, r) = hex("1234").divmod(&hex("123456789abcdef0"));
+        assert!(q.is_zero());
+        assert_eq!(r.to_hex(), "1234");
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn divmod_by_zero() {
+        BigUint::one().divmod(&BigUint::zero());
+    }
+
+    #[test]
+    fn modpow() {
+        assert_eq!(BigUint::from_u64(4).modpow(&BigUint::from_u64(13), &BigUint::from_u64(497)).to_u64(),
+                   Some(445));
+        assert_eq!(hex("123456789abcdef0123456789abcdef")
+                       .modpow(&hex("fedcba9876543210fedcba"), &hex("f123456789abcdef0123456789abcdef1"))
+                       .to_hex(),
+                   "878a142a70b4c3051973710cc4ec6876d");
+        assert!(BigUint::from_u64(5).modpow(&BigUint::zero(), &BigUint::one()).is_zero());
+    }
+
+    #[test]
+    fn modinv() {
+        assert_eq!(BigUint::from_u64(3).modinv(&BigUint::from_u64(11)).and_then(|x| x.to_u64()), Some(4));
+        assert_eq!(BigUint::from_u64(17).modinv(&BigUint::from_u64(3120)).and_then(|x| x.to_u64()), Some(2753));
+        assert_eq!(hex("123456789abcdef0123456789abcdef").modinv(&hex("f123456789abcdef0123456789abcdef1")),
+                   Some(hex("d47c11e5bf30e472d72e3b77c783397f7")));
+        // no inverse when they share a factor
+        assert_eq!(BigUint::from_u64(6).modinv(&BigUint::from_u64(9)), None);
+        assert_eq!(BigUint::zero().modinv(&BigUint::from_u64(7)), None);
+    }
+}
@@ -0,0 +1,112 @@
+// Recovers the key from CBC when the key is also used as the IV (cryptopals
+// challenge 27).
+// Decrypting C1 || 0 || C1 (where 0 is a block of zeros) gives
+//   P1 = D(C1) ^ IV
+//   P3 = D(C1) ^ 0
+// so P1 ^ P3 is the IV. All we need is a receiver that lets us see the plaintext
+// of a ciphertext we chose, e.g. by quoting it back in an error message. If the
+// IV is the key, that's the end of it.
+
+use aes::{self, Aes};
+use cryptoutil;
+use modes;
+use oracle::Oracle;
+use padding;
+
+/// builds C1 || 0 || C1 out of <ciphertext>, followed by its last two blocks so
+/// that the padding still checks out (the receiver might not say anything about
+/// the plaintext otherwise)
+/// returns None if the ciphertext is shorter than two blocks
+pub fn forge_probe(ciphertext: &[u8], block_size: usize) -> Option<Vec<u8>> {
+    if ciphertext.len() < 2 * block_size || ciphertext.len() % block_size != 0 {
+        return None;
+    }
+    let first = &ciphertext[..block_size];
+    let mut probe = first.to_vec();
+    probe.extend(vec![0; block_size]);
+    probe.extend_from_slice(first);
+    probe.extend_from_slice(&ciphertext[ciphertext.len() - 2 * block_size..]);
+    Some(probe)
+}
+
+/// recovers the IV <ciphertext> was encrypted with, by getting <oracle> to
+/// decrypt a probe built from it
+/// returns None if the ciphertext is too short, or the oracle won't show us the
+/// plaintext
+pub fn recover_iv<O: Oracle>(oracle: &mut O, ciphertext: &[u8], block_size: usize) -> Option<Vec<u8>> {
+    let probe = forge_probe(ciphertext, block_size)?;
+    let plaintext = oracle.decrypt(&probe)?;
+    if plaintext.len() < 3 * block_size {
+        return None;
+    }
+    Some(cryptoutil::xor_bytes(&plaintext[..block_size], &plaintext[2 * block_size..3 * block_size]))
+}
+
+/// returns true if <ciphertext> decrypts to properly padded plaintext with
+/// <candidate> as both the AES key and the IV
+/// a wrong key gives valid padding about 1 time in 256, so this is only
+/// trustworthy for ciphertexts of a few blocks; check the plaintext too if it
+/// matters
+pub fn is_key_as_iv(candidate: &[u8], ciphertext: &[u8]) -> bool {
+    if candidate.len() != aes::BLOCK_SIZE || ciphertext.is_empty() || ciphertext.len() % aes::BLOCK_SIZE != 0 {
+        return false;
+    }
+    let padded = modes::cbc_decrypt(&Aes::new(candidate), candidate, ciphertext);
+    padding::pkcs7_unpad(&padded, aes::BLOCK_SIZE).is_ok()
+}
+
+/// recovers the key <ciphertext> was encrypted with, assuming (and checking)
+/// that the key doubles as the IV
+/// returns None if the IV can't be recovered, or it turns out not to be the key
+pub fn recover_key<O: Oracle>(oracle: &mut O, ciphertext: &[u8]) -> Option<Vec<u8>> {
+    let iv = recover_iv(oracle, ciphertext, aes::BLOCK_SIZE)?;
+    if is_key_as_iv(&iv, ciphertext) { Some(iv) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle::{CbcKeyAsIvOracle, FnOracle};
+    use rng::XorShift;
+
+    #[test]
+    fn probe_layout() {
+        let ciphertext: Vec<u8> = (0..48).collect();
+        let probe = forge_probe(&ciphertext, 16).unwrap();
+        assert_eq!(probe[..16], ciphertext[..16]);
+        assert_eq!(probe[16..32], [0; 16]);
+        assert_eq!(probe[32..48], ciphertext[..16]);
+        assert_eq!(probe[48..], ciphertext[16..]);
+        assert_eq!(forge_probe(&ciphertext[..16], 16), None);
+        assert_eq!(forge_probe(&ciphertext[..40], 16), None);
+    }
+
+    #[test]
+    fn recovers_the_key() {
+        for seed in 0..5 {
+            let mut oracle = CbcKeyAsIvOracle::new(XorShift::new(seed));
+            let ciphertext = oracle.encrypt(b"comment1=cooking%20MCs;userdata=;comment2=%20like%20a%20pound%20of%20bacon");
+            let key = recover_key(&mut oracle, &ciphertext);
+            assert_eq!(key.as_ref().map(|k| &k[..]), Some(oracle.key()));
+            // a two-block message is enough too
+            let ciphertext = oracle.encrypt(b"just over a block");
+            assert_eq!(recover_key(&mut oracle, &ciphertext), key);
+            // but a one-block message isn't
+            let ciphertext = oracle.encrypt(b"short");
+            assert_eq!(recover_key(&mut oracle, &ciphertext), None);
+        }
+    }
+
+    #[test]
+    fn notices_a_separate_iv() {
+        // the same key, but with an IV that isn't it
+        let (key, iv) = (b"YELLOW SUBMARINE", [0x27; 16]);
+        let plaintext = padding::pkcs7_pad(&[b'x'; 40], aes::BLOCK_SIZE);
+        let ciphertext = modes::cbc_encrypt(&Aes::new(key), &iv, &plaintext);
+        let mut oracle = FnOracle::new().with_decrypt(|ciphertext| {
+            Some(modes::cbc_decrypt(&Aes::new(key), &iv, ciphertext))
+        });
+        assert_eq!(recover_iv(&mut oracle, &ciphertext, aes::BLOCK_SIZE), Some(iv.to_vec()));
+        assert_eq!(recover_key(&mut oracle, &ciphertext), None);
+    }
+}
@@ -0,0 +1,332 @@
+// Classical alphabetic ciphers: caesar, vigenere, affine and monoalphabetic
+// substitution. All of them only touch ascii letters (preserving case) and pass
+// everything else through untouched, which is how these ciphers are usually
+// presented in puzzles.
+// The breakers are the same frequency analysis we used on single-byte and
+// repeating-key XOR in set 1, just over the alphabet instead of over bytes.
+
+use std::ops::Range;
+use cryptoutil;
+
+/// given a letter, returns its position in the alphabet (0-25), or None if it
+/// isn't an ascii letter
+fn letter_index(c: char) -> Option<u8> {
+    if c.is_ascii_alphabetic() {
+        Some(c.to_ascii_lowercase() as u8 - b'a')
+    } else {
+        None
+    }
+}
+
+/// given a position in the alphabet, returns the letter in the same case as <like>
+fn index_letter(i: u8, like: char) -> char {
+    let base = if like.is_ascii_uppercase() { b'A' } else { b'a' };
+    (base + i % 26) as char
+}
+
+/// applies <f> to the alphabet index of every letter in <text>, where the second
+/// argument to <f> is the number of letters seen so far
+/// non-letters are copied over unchanged (and don't count as letters)
+fn map_letters<F>(text: &str, mut f: F) -> String
+    where F: FnMut(u8, usize) -> u8 {
+    let mut n = 0;
+    text.chars().map(|c| match letter_index(c) {
+        Some(i) => {
+            let mapped = index_letter(f(i, n), c);
+            n += 1;
+            mapped
+        },
+        None => c,
+    }).collect()
+}
+
+/// given a string, returns just the letters in it as alphabet indices
+fn letters_only(text: &str) -> Vec<u8> {
+    text.chars().filter_map(letter_index).collect()
+}
+
+/// given a key made of letters, returns the shift for each letter
+/// panics if the key is empty or contains something other than letters
+fn key_shifts(key: &str) -> Vec<u8> {
+    let shifts: Vec<u8> = key.chars()
+        .map(|c| letter_index(c).unwrap_or_else(|| panic!("key must be all letters: {}", key)))
+        .collect();
+    assert!(!shifts.is_empty());
+    shifts
+}
+
+/// encrypts <text> by shifting every letter <shift> places down the alphabet
+pub fn caesar_encrypt(text: &str, shift: u8) -> String {
+    map_letters(text, |i, _| (i + shift % 26) % 26)
+}
+
+/// undoes `caesar_encrypt`
+pub fn caesar_decrypt(text: &str, shift: u8) -> String {
+    caesar_encrypt(text, 26 - shift % 26)
+}
+
+/// breaks a caesar cipher by trying all 26 shifts and keeping the one whose
+/// plaintext looks the most like english
+/// returns (shift, plaintext)
+pub fn break_caesar(ciphertext: &str) -> (u8, String) {
+    (0..26).map(|shift| (shift, caesar_decrypt(ciphertext, shift)))
+           .min_by(|a, b| cryptoutil::english_chi_square(&a.1)
+                              .total_cmp(&cryptoutil::english_chi_square(&b.1)))
+           .unwrap()
+}
+
+/// encrypts <text> with the vigenere cipher, i.e. the nth letter is shifted by
+/// the (n mod key length)th letter of the key. 'a' is a shift of 0.
+pub fn vigenere_encrypt(text: &str, key: &str) -> String {
+    let shifts = key_shifts(key);
+    map_letters(text, |i, n| (i + shifts[n % shifts.len()]) % 26)
+}
+
+/// undoes `vigenere_encrypt`
+pub fn vigenere_decrypt(text: &str, key: &str) -> String {
+    let shifts = key_shifts(key);
+    map_letters(text, |i, n| (i + 26 - shifts[n % shifts.len()]) % 26)
+}
+
+/// breaks a vigenere cipher the same way challenge 1-6 breaks repeating-key XOR:
+/// rank the key sizes in <keysizes> by normalized hamming distance, split the
+/// letters into one column per key letter, and solve each column as a caesar
+/// cipher
+/// The hamming distance here counts differing letters rather than differing bits,
+/// since shifting letters doesn't preserve anything at the bit level. Even so, a
+/// multiple of the real key size ranks about as well as the real one, so the top
+/// few key sizes are all tried and the one that decrypts to the most
+/// english-looking plaintext wins.
+/// returns (key, plaintext)
+pub fn break_vigenere(ciphertext: &str, keysizes: Range<usize>) -> (String, String) {
+    // only the letters are considered, so that punctuation and spacing in the
+    // ciphertext doesn't throw off the blocks
+    let letters = letters_only(ciphertext);
+    let ranked = cryptoutil::rank_keysizes_by(&letters, keysizes, |s, t| {
+        s.iter().zip(t.iter()).filter(|&(a, b)| a != b).count() as u32
+    });
+
+    let mut candidates = vec![];
+    for &(key_size, _) in ranked.iter().take(5) {
+        let mut key: String = cryptoutil::transpose(&letters, key_size).iter()
+            .map(|column| {
+                let column: String = column.iter().map(|&i| index_letter(i, 'a')).collect();
+                index_letter(break_caesar(&column).0, 'a')
+            })
+            .collect();
+        // if we picked a multiple of the real key size, the key will just be the
+        // real key repeated, so cut it down to size
+        let period = (1..key_size + 1)
+            .find(|&d| key_size % d == 0 && key.as_bytes().chunks(d).all(|c| c == &key.as_bytes()[..d]))
+            .unwrap();
+        key.truncate(period);
+        let plaintext = vigenere_decrypt(ciphertext, &key);
+        candidates.push((cryptoutil::english_chi_square(&plaintext), key, plaintext));
+    }
+    // different key sizes can still end up with the same key, so ties go to the
+    // shorter one
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.len().cmp(&b.1.len())));
+    let (_, key, plaintext) = candidates.into_iter().next()
+                                        .expect("ciphertext is too short to break");
+    (key, plaintext)
+}
+
+/// returns the multiplicative inverse of <a> mod 26, or None if <a> shares a
+/// factor with 26 (in which case it can't be used as an affine key)
+pub fn affine_inverse(a: u8) -> Option<u8> {
+    (1..26).find(|&x| (a as u32 % 26) * x as u32 % 26 == 1)
+}
+
+/// encrypts <text> with the affine cipher: each letter i becomes a*i + b mod 26
+/// panics if <a> isn't coprime with 26
+pub fn affine_encrypt(text: &str, a: u8, b: u8) -> String {
+    assert!(affine_inverse(a).is_some(), "{} is not coprime with 26", a);
+    map_letters(text, |i, _| ((a as u32 * i as u32 + b as u32) % 26) as u8)
+}
+
+/// undoes `affine_encrypt`
+pub fn affine_decrypt(text: &str, a: u8, b: u8) -> String {
+    let a_inv = affine_inverse(a).unwrap_or_else(|| panic!("{} is not coprime with 26", a));
+    map_letters(text, |i, _| ((a_inv as u32 * (i as u32 + 26 - b as u32 % 26)) % 26) as u8)
+}
+
+/// breaks an affine cipher by trying all 312 keys and keeping the one whose
+/// plaintext looks the most like english
+/// returns ((a, b), plaintext)
+pub fn break_affine(ciphertext: &str) -> ((u8, u8), String) {
+    let mut best_chi_square = ::std::f64::INFINITY;
+    let mut best = ((1, 0), ciphertext.to_string());
+    for a in (1..26).filter(|&a| affine_inverse(a).is_some()) {
+        for b in 0..26 {
+            let plaintext = affine_decrypt(ciphertext, a, b);
+            let chi_square = cryptoutil::english_chi_square(&plaintext);
+            if chi_square < best_chi_square {
+                best_chi_square = chi_square;
+                best = ((a, b), plaintext);
+            }
+        }
+    }
+    best
+}
+
+/// checks that <key> is a valid substitution key (a permutation of the 26
+/// letters) and returns it as alphabet indices
+fn substitution_indices(key: &str) -> Vec<u8> {
+    let indices = key_shifts(key);
+    let mut seen = [false; 26];
+    for &i in &indices {
+        seen[i as usize] = true;
+    }
+    assert!(indices.len() == 26 && seen.iter().all(|&x| x),
+            "substitution key must contain every letter exactly once: {}", key);
+    indices
+}
+
+/// encrypts <text> with a monoalphabetic substitution cipher
+/// <key> is the ciphertext alphabet, i.e. 'a' becomes the first letter of the
+/// key, 'b' becomes the second, and so on
+pub fn substitution_encrypt(text: &str, key: &str) -> String {
+    let indices = substitution_indices(key);
+    map_letters(text, |i, _| indices[i as usize])
+}
+
+/// undoes `substitution_encrypt`
+pub fn substitution_decrypt(text: &str, key: &str) -> String {
+    substitution_encrypt(text, &invert_substitution_key(key))
+}
+
+/// given a substitution key, returns the key that undoes it
+pub fn invert_substitution_key(key: &str) -> String {
+    let indices = substitution_indices(key);
+    let mut inverse = vec!['a'; 26];
+    for (plain, &cipher) in indices.iter().enumerate() {
+        inverse[cipher as usize] = index_letter(plain as u8, 'a');
+    }
+    inverse.into_iter().collect()
+}
+
+/// guesses a substitution key by matching the ciphertext letters ranked by
+/// frequency against `LETTER_FREQS` ranked by frequency (the approach `main1`
+/// takes in challenge 1-3)
+/// this is only ever approximately right, but it's a good starting point
+pub fn guess_substitution_key(ciphertext: &str) -> String {
+    let mut counts = [0u32; 26];
+    for i in letters_only(ciphertext) {
+        counts[i as usize] += 1;
+    }
+    let mut cipher_ranked: Vec<u8> = (0..26).collect();
+    cipher_ranked.sort_by(|a, b| counts[*b as usize].cmp(&counts[*a as usize]).then(a.cmp(b)));
+    let mut english_ranked: Vec<&(char, f64)> = cryptoutil::LETTER_FREQS.iter().collect();
+    english_ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut key = vec!['a'; 26];
+    for (&&(plain, _), &cipher) in english_ranked.iter().zip(cipher_ranked.iter()) {
+        key[letter_index(plain).unwrap() as usize] = index_letter(cipher, 'a');
+    }
+    key.into_iter().collect()
+}
+
+/// breaks a substitution cipher using `guess_substitution_key`
+/// returns (key, plaintext)
+pub fn break_substitution(ciphertext: &str) -> (String, String) {
+    let key = guess_substitution_key(ciphertext);
+    let plaintext = substitution_decrypt(ciphertext, &key);
+    (key, plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ngram::ENGLISH_CORPUS;
+
+    const SHORT: &str = "Meet me at the Old Bridge at 10pm, and come alone! Bring the documents \
+                         we discussed last week, and make sure that nobody follows you there.";
+
+    /// the fraction of letters that <guess> gets right
+    fn letter_accuracy(guess: &str, truth: &str) -> f64 {
+        let (guess, truth) = (letters_only(guess), letters_only(truth));
+        guess.iter().zip(&truth).filter(|&(a, b)| a == b).count() as f64 / truth.len() as f64
+    }
+
+    #[test]
+    fn caesar_round_trip() {
+        assert_eq!(caesar_encrypt("Hello, World!", 3), "Khoor, Zruog!");
+        for shift in 0..30 {
+            assert_eq!(caesar_decrypt(&caesar_encrypt(SHORT, shift), shift), SHORT);
+        }
+    }
+
+    #[test]
+    fn caesar_break() {
+        assert_eq!(break_caesar(&caesar_encrypt(SHORT, 11)), (11, SHORT.to_string()));
+        // long enough that the chi-square test doesn't pool any bins
+        assert_eq!(break_caesar(&caesar_encrypt(ENGLISH_CORPUS, 19)), (19, ENGLISH_CORPUS.to_string()));
+    }
+
+    #[test]
+    fn vigenere_round_trip() {
+        assert_eq!(vigenere_encrypt("ATTACK AT DAWN", "lemon"), "LXFOPV EF RNHR");
+        assert_eq!(vigenere_decrypt(&vigenere_encrypt(SHORT, "Key"), "kEY"), SHORT);
+    }
+
+    #[test]
+    #[should_panic(expected = "key must be all letters: lem0n")]
+    fn vigenere_rejects_bad_key() {
+        vigenere_encrypt("hello", "lem0n");
+    }
+
+    #[test]
+    fn vigenere_break() {
+        let ciphertext = vigenere_encrypt(ENGLISH_CORPUS, "austen");
+        assert_eq!(break_vigenere(&ciphertext, 2..20), ("austen".to_string(), ENGLISH_CORPUS.to_string()));
+        let short = &ENGLISH_CORPUS[..2000];
+        let ciphertext = vigenere_encrypt(short, "pride");
+        assert_eq!(break_vigenere(&ciphertext, 2..12), ("pride".to_string(), short.to_string()));
+    }
+
+    #[test]
+    fn affine_round_trip() {
+        assert_eq!(affine_inverse(13), None);
+        assert_eq!(affine_inverse(7), Some(15));
+        assert_eq!(affine_encrypt("affine cipher", 5, 8), "ihhwvc swfrcp");
+        for a in (1..26).filter(|&a| affine_inverse(a).is_some()) {
+            assert_eq!(affine_decrypt(&affine_encrypt(SHORT, a, 20), a, 20), SHORT);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "13 is not coprime with 26")]
+    fn affine_rejects_bad_multiplier() {
+        affine_decrypt("hello", 13, 0);
+    }
+
+    #[test]
+    fn affine_break() {
+        assert_eq!(break_affine(&affine_encrypt(SHORT, 7, 3)), ((7, 3), SHORT.to_string()));
+        assert_eq!(break_affine(&affine_encrypt(ENGLISH_CORPUS, 21, 12)), ((21, 12), ENGLISH_CORPUS.to_string()));
+    }
+
+    #[test]
+    fn substitution_round_trip() {
+        let key = "qwertyuiopasdfghjklzxcvbnm";
+        assert_eq!(substitution_encrypt("Hello", key), "Itssg");
+        assert_eq!(substitution_decrypt(&substitution_encrypt(SHORT, key), key), SHORT);
+        assert_eq!(invert_substitution_key(&invert_substitution_key(key)), key);
+    }
+
+    #[test]
+    #[should_panic]
+    fn substitution_rejects_bad_key() {
+        substitution_encrypt("hello", "abcdefghijklmnopqrstuvwxya");
+    }
+
+    #[test]
+    fn substitution_break() {
+        // frequency ranking alone only gets part of the way, but it gets the most
+        // common letters right, and those make up most of the text
+        let key = "zyxwvutsrqponmlkjihgfedcba";
+        let (guess, plaintext) = break_substitution(&substitution_encrypt(ENGLISH_CORPUS, key));
+        assert_eq!(&guess[4..5], "v");
+        assert!(letter_accuracy(&plaintext, ENGLISH_CORPUS) > 0.4);
+    }
+}
@@ -1,8 +1,10 @@
 use std::char;
 use std::cmp;
+use std::cmp::Ordering::Less;
 use std::collections::HashMap;
 use std::ascii::AsciiExt;
 use std::fmt::Write;
+use std::ops::Range;
 
 /// from challenge 1-1
 /// given a string representing a hex value, returns a string representing the
@@ -125,6 +127,22 @@ pub fn hex_to_hex_xor(buf1: &str, buf2: &str) -> String {
     s
 }
 
+/// from challenge 1-2
+/// given two equal-length byte slices, returns their XOR
+pub fn xor_bytes(buf1: &[u8], buf2: &[u8]) -> Vec<u8> {
+    assert_eq!(buf1.len(), buf2.len());
+    buf1.iter().zip(buf2.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+/// from challenge 1-5
+/// given a buffer and a key, returns the buffer XORed against the key repeated
+/// over its whole length
+/// (when the key is as long as the buffer, this is just `xor_bytes`)
+pub fn repeating_key_xor(buf: &[u8], key: &[u8]) -> Vec<u8> {
+    assert!(!key.is_empty());
+    buf.iter().zip(key.iter().cycle()).map(|(a, b)| a ^ b).collect()
+}
+
 /// from challenge 1-2
 /// given a hex-encoded string, returns the decoded ascii string
 /// (note that the string will actually be a unicode (UTF-8 encoded) string since
@@ -155,6 +173,13 @@ pub fn ascii_to_hex(s: &str) -> String {
     t
 }
 
+/// from challenge 4-27
+/// returns true if every byte in <buf> is part of the original ascii charset
+/// (the same check `ascii_to_hex` asserts on, but for raw bytes)
+pub fn is_ascii(buf: &[u8]) -> bool {
+    buf.iter().all(|&b| b < 0x80)
+}
+
 /// from challenge 1-3
 /// given a hex-encoded string, returns a hashmap where keys are strings representing
 /// byte values, and values are the frequencies of those byte values in the input string
@@ -206,6 +231,302 @@ pub fn ascii_top_freq(s: &str, n: usize) -> Vec<char> {
     chars
 }
 
-pub fn slice_iter(s: &str, n: usize) -> Iterator?? {
-    unimplemented!();
+/// given a string, returns an iterator over consecutive slices of that string
+/// which are <n> bytes long (the last slice may be shorter)
+/// this is the "gnarly iterator" from challenges 1-1 and 1-6, finally in one place
+pub fn slice_iter<'a>(s: &'a str, n: usize) -> impl Iterator<Item=&'a str> + 'a {
+    assert!(n > 0);
+    (0..s.len()).filter(move |&x| x % n == 0).map(move |i| &s[i..cmp::min(i+n, s.len())])
+}
+
+/// given a hex-encoded string, returns the decoded bytes
+pub fn hex_to_bytes(s: &str) -> Vec<u8> {
+    assert!(s.len() % 2 == 0);
+    slice_iter(s, 2).map(|byte| u8::from_str_radix(byte, 16)
+                                    .expect(&format!("not a hex value: {}", byte)))
+                    .collect()
+}
+
+/// given a slice of bytes, returns the hex-encoded string
+pub fn bytes_to_hex(buf: &[u8]) -> String {
+    let mut s = String::new();
+    for b in buf {
+        write!(&mut s, "{:02x}", b).unwrap();
+    }
+    s
+}
+
+/// given a base64-encoded string, returns the decoded bytes
+pub fn base64_to_bytes(s: &str) -> Vec<u8> {
+    hex_to_bytes(&base64_to_hex(s))
+}
+
+/// from challenge 1-6
+/// given a buffer, returns <n> columns where column i holds every byte whose
+/// index is i mod n, in order
+/// in other words, this splits the buffer into rows of <n> bytes (the last row may
+/// be short) and transposes that matrix. For a ciphertext under an n-byte
+/// repeating key, each column is then encrypted with a single key byte.
+pub fn transpose(buf: &[u8], n: usize) -> Vec<Vec<u8>> {
+    assert!(n > 0);
+    let mut columns = vec![Vec::with_capacity(buf.len() / n + 1); n];
+    for (i, &b) in buf.iter().enumerate() {
+        columns[i % n].push(b);
+    }
+    columns
+}
+
+/// the inverse of `transpose`: given the columns, reads the matrix back out row
+/// by row
+/// the columns must come from a valid transposition, i.e. no column may be more
+/// than one byte longer than any column after it
+pub fn untranspose(columns: &[Vec<u8>]) -> Vec<u8> {
+    let len = columns.iter().map(|c| c.len()).sum();
+    let mut buf = Vec::with_capacity(len);
+    for row in 0.. {
+        if buf.len() == len {
+            break;
+        }
+        for column in columns {
+            if let Some(&b) = column.get(row) {
+                buf.push(b);
+            }
+        }
+    }
+    buf
+}
+
+/// from challenge 1-6
+/// the frequency (in percent) of each letter in english text
+pub const LETTER_FREQS: [(char, f64); 26] = [
+    ('a', 8.16), ('b', 1.49), ('c', 2.78), ('d', 4.25), ('e', 12.7), ('f', 2.22),
+    ('g', 2.02), ('h', 6.09), ('i', 6.97), ('j', 0.15), ('k', 0.77), ('l', 4.03),
+    ('m', 2.41), ('n', 6.75), ('o', 7.51), ('p', 1.93), ('q', 0.10), ('r', 5.99),
+    ('s', 6.44), ('t', 9.01), ('u', 2.76), ('v', 0.98), ('w', 2.36), ('x', 0.15),
+    ('y', 1.97), ('z', 0.07)
+];
+
+/// from challenge 1-6
+/// computes the hamming distance between two equal-length byte slices
+/// that is, this function returns the number of differing bits between them
+pub fn hamming_distance(s: &[u8], t: &[u8]) -> u32 {
+    assert_eq!(s.len(), t.len());
+    s.iter().zip(t.iter()).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
+
+/// from challenge 1-6
+/// given a ciphertext encrypted with some repeating key, returns a vector of
+/// (key size, normalized hamming distance) for every key size in <range>, sorted
+/// so that the most likely key size comes first
+/// the distance is averaged over every pair of consecutive key-sized blocks, since
+/// only comparing the first two blocks (like I originally did) is way too noisy
+pub fn rank_keysizes(buf: &[u8], range: Range<usize>) -> Vec<(usize, f64)> {
+    rank_keysizes_by(buf, range, hamming_distance)
+}
+
+/// same as `rank_keysizes`, but with a custom distance between two blocks
+/// useful when the cipher isn't XOR, e.g. counting the positions where two blocks
+/// differ works much better than counting differing bits for alphabetic ciphers
+pub fn rank_keysizes_by<F>(buf: &[u8], range: Range<usize>, distance: F) -> Vec<(usize, f64)>
+    where F: Fn(&[u8], &[u8]) -> u32 {
+    let mut v = vec![];
+    for key_size in range {
+        let blocks: Vec<&[u8]> = buf.chunks(key_size).filter(|b| b.len() == key_size).collect();
+        if blocks.len() < 2 {
+            continue;
+        }
+        let total: u32 = blocks.windows(2).map(|w| distance(w[0], w[1])).sum();
+        let pairs = (blocks.len() - 1) as f64;
+        v.push((key_size, (total as f64) / pairs / (key_size as f64)));
+    }
+    v.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Less));
+    v
+}
+
+/// the percentage of characters in english text which are spaces
+pub const SPACE_FREQ: f64 = 17.0;
+
+/// the percentage of characters in english text which are printable, but neither
+/// letters nor spaces (punctuation, digits, newlines...)
+/// the letter frequencies in `LETTER_FREQS` are scaled down to make room for
+/// this and `SPACE_FREQ`
+pub const OTHER_FREQ: f64 = 3.0;
+
+/// the amount added to `english_chi_square` for every unprintable character
+pub const UNPRINTABLE_PENALTY: f64 = 100.0;
+
+/// from challenge 1-6
+/// given a string, returns the chi-square statistic comparing its character
+/// frequencies (case-insensitive) against english
+/// lower is more english-like. The expected counts are based on the total number
+/// of characters in the string, so text that's mostly non-letters (e.g. garbage
+/// from decrypting with the wrong key) scores badly.
+/// Control characters and non-ascii characters never show up in english, so
+/// each one adds a flat `UNPRINTABLE_PENALTY`. Without this, XOR keys that only
+/// differ in case (0x20) score the same, since they just flip the case of every
+/// letter (and turn spaces into NULs).
+pub fn english_chi_square(s: &str) -> f64 {
+    let len = s.chars().count();
+    if len == 0 {
+        return ::std::f64::INFINITY;
+    }
+    // bins 0-25 are letters, 26 is spaces, 27 is everything else printable
+    let mut counts = [0u32; 28];
+    let mut unprintable = 0;
+    for c in s.chars() {
+        if c.is_ascii_alphabetic() {
+            counts[(c.to_ascii_lowercase() as u8 - b'a') as usize] += 1;
+        } else if c == ' ' {
+            counts[26] += 1;
+        } else if c.is_ascii_graphic() || c == '\n' || c == '\r' || c == '\t' {
+            counts[27] += 1;
+        } else {
+            unprintable += 1;
+        }
+    }
+    let len = len as f64;
+    let letter_share = (100.0 - SPACE_FREQ - OTHER_FREQ) / 100.0;
+    let expected = LETTER_FREQS.iter()
+                               .map(|&(_, percent)| len * letter_share * percent / 100.0)
+                               .chain(vec![len * SPACE_FREQ / 100.0, len * OTHER_FREQ / 100.0]);
+
+    // the chi-square test falls apart when a bin expects only a handful of
+    // observations (a couple of 'z's in a short string would otherwise dominate
+    // the score), so all the bins expecting fewer than 5 get pooled into one
+    let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+    let mut chi_square = (unprintable as f64) * UNPRINTABLE_PENALTY;
+    for (&observed, expected) in counts.iter().zip(expected) {
+        if expected < 5.0 {
+            pooled_observed += observed as f64;
+            pooled_expected += expected;
+        } else {
+            let diff = (observed as f64) - expected;
+            chi_square += diff * diff / expected;
+        }
+    }
+    // long enough strings have nothing to pool (every bin expects at least 5)
+    if pooled_expected > 0.0 {
+        let diff = pooled_observed - pooled_expected;
+        chi_square += diff * diff / pooled_expected;
+    }
+    chi_square
+}
+
+/// the fraction of letters in english text which are capitals (a rough guess)
+pub const CAPITAL_SHARE: f64 = 0.05;
+
+/// the log10 probability `english_log_likelihood` gives each unprintable character
+pub const UNPRINTABLE_LOG_PROB: f64 = -6.0;
+
+/// given a string, returns the log10 probability of getting its characters by
+/// drawing each one independently from english text (higher is more english-like)
+/// unlike `english_chi_square`, this doesn't need enough characters to fill out a
+/// histogram, so it still works on a few dozen characters, which is all you get
+/// from a column of short ciphertexts. It also tells capitals apart from lowercase
+/// letters, since XOR keys that only differ in 0x20 swap one for the other.
+pub fn english_log_likelihood(s: &str) -> f64 {
+    let letter_share = (100.0 - SPACE_FREQ - OTHER_FREQ) / 100.0;
+    // everything printable that isn't a letter or space: 33 punctuation marks, 10
+    // digits and 3 kinds of whitespace
+    let other = (OTHER_FREQ / 100.0 / 46.0).log10();
+    s.chars().map(|c| {
+        if c.is_ascii_alphabetic() {
+            let percent = LETTER_FREQS[(c.to_ascii_lowercase() as u8 - b'a') as usize].1;
+            let case = if c.is_ascii_uppercase() { CAPITAL_SHARE } else { 1.0 - CAPITAL_SHARE };
+            (letter_share * percent / 100.0 * case).log10()
+        } else if c == ' ' {
+            (SPACE_FREQ / 100.0).log10()
+        } else if c.is_ascii_graphic() || c == '\n' || c == '\r' || c == '\t' {
+            other
+        } else {
+            UNPRINTABLE_LOG_PROB
+        }
+    }).sum()
+}
+
+/// from challenge 1-3
+/// given a buffer where every byte was XORed with the same key byte, tries every
+/// possible key and returns (key, score) for the one whose plaintext looks the
+/// most like english (see `english_log_likelihood`)
+/// this is the building block for breaking anything that boils down to a pile of
+/// single-byte XORs: repeating-key XOR, a reused CTR keystream...
+pub fn break_single_byte_xor(buf: &[u8]) -> (u8, f64) {
+    let mut best = (0, ::std::f64::NEG_INFINITY);
+    for key in 0..256 {
+        let key = key as u8;
+        let plaintext: String = buf.iter().map(|&b| (b ^ key) as char).collect();
+        let score = english_log_likelihood(&plaintext);
+        if score > best.1 {
+            best = (key, score);
+        }
+    }
+    best
+}
+
+/// returns true if <a> and <b> are equal, taking the same time no matter where
+/// (or whether) they differ
+/// use this for anything secret, like MACs. The lengths aren't treated as secret:
+/// slices of different lengths are rejected straight away.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    // OR together every difference instead of stopping at the first one
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    ::std::hint::black_box(diff) == 0
+}
+
+/// returns a copy of <a> if <choice> is true, and of <b> otherwise, without
+/// branching on <choice>
+pub fn constant_time_select(choice: bool, a: &[u8], b: &[u8]) -> Vec<u8> {
+    assert_eq!(a.len(), b.len());
+    // all ones if choice is true, all zeros otherwise
+    let mask = (::std::hint::black_box(choice) as u8).wrapping_neg();
+    a.iter().zip(b).map(|(x, y)| (x & mask) | (y & !mask)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ngram::ENGLISH_CORPUS;
+
+    #[test]
+    fn chi_square_empty_text() {
+        assert_eq!(english_chi_square(""), ::std::f64::INFINITY);
+    }
+
+    #[test]
+    fn chi_square_long_text() {
+        // long enough that no bin gets pooled, which used to divide 0 by 0
+        let text = ENGLISH_CORPUS.repeat(2);
+        let chi_square = english_chi_square(&text);
+        assert!(chi_square.is_finite());
+        // and it still tells english from english with its letters scrambled
+        let scrambled: Vec<u8> = text.bytes().enumerate().map(|(i, b)| b ^ [0x05, 0x0a][i % 2]).collect();
+        assert!(chi_square < english_chi_square(&String::from_utf8_lossy(&scrambled)));
+    }
+
+    #[test]
+    fn constant_time_eq_compares() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"YELLOW SUBMARINE", b"YELLOW SUBMARINE"));
+        assert!(!constant_time_eq(b"YELLOW SUBMARINE", b"yELLOW SUBMARINE"));
+        assert!(!constant_time_eq(b"YELLOW SUBMARINE", b"YELLOW SUBMARINe"));
+        assert!(!constant_time_eq(b"YELLOW SUBMARINE", b"YELLOW"));
+        assert!(!constant_time_eq(b"", b"\x00"));
+    }
+
+    #[test]
+    fn constant_time_select_picks() {
+        assert_eq!(constant_time_select(true, b"abc", b"xyz"), b"abc".to_vec());
+        assert_eq!(constant_time_select(false, b"abc", b"xyz"), b"xyz".to_vec());
+        assert_eq!(constant_time_select(true, &[0xff, 0x00], &[0x00, 0xff]), vec![0xff, 0x00]);
+        assert_eq!(constant_time_select(false, b"", b""), vec![]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn constant_time_select_needs_equal_lengths() {
+        constant_time_select(true, b"abc", b"xy");
+    }
 }
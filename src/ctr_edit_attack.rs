@@ -0,0 +1,67 @@
+// Recovers CTR plaintext through an oracle that lets us edit ciphertexts in
+// place (cryptopals challenge 25).
+// Editing re-encrypts the new plaintext with the same keystream bytes the old
+// plaintext used. So if we "edit" a ciphertext to be all zeros, what comes back
+// is the keystream itself, and XORing that with the original ciphertext gives us
+// the plaintext. We never need the key.
+
+use cryptoutil;
+use oracle::Oracle;
+
+/// recovers the keystream under <ciphertext>, editing at most <max_edit> bytes
+/// per query (some oracles only accept small edits; pass ciphertext.len() if
+/// there's no limit)
+pub fn recover_keystream<O: Oracle>(oracle: &mut O, ciphertext: &[u8], max_edit: usize) -> Vec<u8> {
+    assert!(max_edit > 0);
+    let mut keystream = Vec::with_capacity(ciphertext.len());
+    while keystream.len() < ciphertext.len() {
+        let offset = keystream.len();
+        let len = ::std::cmp::min(max_edit, ciphertext.len() - offset);
+        let edited = oracle.edit(ciphertext, offset, &vec![0; len]);
+        keystream.extend_from_slice(&edited[offset..offset + len]);
+    }
+    keystream
+}
+
+/// recovers the plaintext of <ciphertext>, with a single edit query
+pub fn recover_plaintext<O: Oracle>(oracle: &mut O, ciphertext: &[u8]) -> Vec<u8> {
+    let keystream = recover_keystream(oracle, ciphertext, ciphertext.len().max(1));
+    cryptoutil::xor_bytes(ciphertext, &keystream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use oracle::{CtrEditOracle, FnOracle};
+    use rng::XorShift;
+
+    const PLAINTEXT: &'static [u8] = b"I'm back and I'm ringin' the bell, a rockin' on the mike while the fly girls yell";
+
+    #[test]
+    fn recovers_plaintext() {
+        let mut oracle = CtrEditOracle::new(XorShift::new(39));
+        let ciphertext = oracle.encrypt(PLAINTEXT);
+        assert_eq!(recover_plaintext(&mut oracle, &ciphertext), PLAINTEXT.to_vec());
+        assert_eq!(recover_plaintext(&mut oracle, &[]), vec![]);
+    }
+
+    #[test]
+    fn small_edits() {
+        let mut ctr = CtrEditOracle::new(XorShift::new(39));
+        let ciphertext = ctr.encrypt(PLAINTEXT);
+        for &max_edit in &[1, 3, 16, 17] {
+            let queries = Cell::new(0);
+            let keystream = {
+                let mut oracle = FnOracle::new().with_edit(|ciphertext, offset, newtext| {
+                    assert!(newtext.len() <= max_edit);
+                    queries.set(queries.get() + 1);
+                    ctr.edit(ciphertext, offset, newtext)
+                });
+                recover_keystream(&mut oracle, &ciphertext, max_edit)
+            };
+            assert_eq!(cryptoutil::xor_bytes(&ciphertext, &keystream), PLAINTEXT.to_vec());
+            assert_eq!(queries.get(), (PLAINTEXT.len() + max_edit - 1) / max_edit);
+        }
+    }
+}
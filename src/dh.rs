@@ -0,0 +1,183 @@
+// Finite-field Diffie-Hellman (cryptopals challenge 33).
+// Both sides pick a secret exponent, publish g^secret mod p, and raise the other
+// side's public value to their own secret, which gets both of them g^(ab) mod p.
+// Nothing here checks the public values it's given, so a man in the middle who
+// swaps them (or the group) for something degenerate controls the shared secret.
+
+use bignum::BigUint;
+use rng::RandomSource;
+use sha1;
+
+/// the 1536-bit MODP prime (RFC 3526 group 5), which challenge 33 calls "the
+/// NIST prime"
+const MODP_1536_P: &str = concat!(
+    "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74",
+    "020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f1437",
+    "4fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7ed",
+    "ee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf05",
+    "98da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb",
+    "9ed529077096966d670c354e4abc9804f1746c08ca237327ffffffffffffffff",
+);
+
+/// the 2048-bit MODP prime (RFC 3526 group 14)
+const MODP_2048_P: &str = concat!(
+    "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74",
+    "020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f1437",
+    "4fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7ed",
+    "ee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf05",
+    "98da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb",
+    "9ed529077096966d670c354e4abc9804f1746c08ca18217c32905e462e36ce3b",
+    "e39e772c180e86039b2783a2ec07a28fb5c55df06f4c52c9de2bcbf695581718",
+    "3995497cea956ae515d2261898fa051015728e5a8aacaa68ffffffffffffffff",
+);
+
+/// the 3072-bit MODP prime (RFC 3526 group 15)
+const MODP_3072_P: &str = concat!(
+    "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74",
+    "020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f1437",
+    "4fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7ed",
+    "ee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf05",
+    "98da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb",
+    "9ed529077096966d670c354e4abc9804f1746c08ca18217c32905e462e36ce3b",
+    "e39e772c180e86039b2783a2ec07a28fb5c55df06f4c52c9de2bcbf695581718",
+    "3995497cea956ae515d2261898fa051015728e5a8aaac42dad33170d04507a33",
+    "a85521abdf1cba64ecfb850458dbef0a8aea71575d060c7db3970f85a6e1e4c7",
+    "abf5ae8cdb0933d71e8c94e04a25619dcee3d2261ad2ee6bf12ffa06d98a0864",
+    "d87602733ec86a64521f2b18177b200cbbe117577a615d6c770988c0bad946e2",
+    "08e24fa074e5ab3143db5bfce0fd108e4b82d120a93ad2caffffffffffffffff",
+);
+
+/// the length in bytes of a session key from `session_key` (an AES-128 key)
+pub const SESSION_KEY_LEN: usize = 16;
+
+/// a prime modulus and a generator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub p: BigUint,
+    pub g: BigUint,
+}
+
+impl Group {
+    pub fn new(p: BigUint, g: BigUint) -> Group {
+        Group { p: p, g: g }
+    }
+
+    /// the RFC 3526 group 5 (1536-bit prime, g = 2)
+    pub fn modp_1536() -> Group {
+        Group::new(BigUint::from_hex(MODP_1536_P), BigUint::from_u64(2))
+    }
+
+    /// the RFC 3526 group 14 (2048-bit prime, g = 2)
+    pub fn modp_2048() -> Group {
+        Group::new(BigUint::from_hex(MODP_2048_P), BigUint::from_u64(2))
+    }
+
+    /// the RFC 3526 group 15 (3072-bit prime, g = 2)
+    pub fn modp_3072() -> Group {
+        Group::new(BigUint::from_hex(MODP_3072_P), BigUint::from_u64(2))
+    }
+
+    /// the number of bytes it takes to write down any element of the group
+    pub fn element_len(&self) -> usize {
+        (self.p.bits() + 7) / 8
+    }
+
+    /// generates a key pair with a random private exponent in [1, p-1)
+    pub fn keypair<R: RandomSource>(&self, rng: &mut R) -> KeyPair {
+        let range = &self.p - &BigUint::from_u64(2);
+        let private = &BigUint::random_below(&range, rng) + &BigUint::one();
+        KeyPair::from_private(self, private)
+    }
+}
+
+/// one side's half of a key exchange
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    private: BigUint,
+    public: BigUint,
+}
+
+impl KeyPair {
+    /// the key pair with private exponent <private> in <group>
+    pub fn from_private(group: &Group, private: BigUint) -> KeyPair {
+        let public = group.g.modpow(&private, &group.p);
+        KeyPair { private: private, public: public }
+    }
+
+    pub fn private(&self) -> &BigUint {
+        &self.private
+    }
+
+    /// the value to send to the other side
+    pub fn public(&self) -> &BigUint {
+        &self.public
+    }
+
+    /// combines our private key with <their_public> to get the shared secret
+    pub fn shared_secret(&self, group: &Group, their_public: &BigUint) -> BigUint {
+        their_public.modpow(&self.private, &group.p)
+    }
+}
+
+/// from challenge 5-34
+/// derives an AES-128 key from a shared secret: the first 16 bytes of the SHA-1
+/// of its big-endian bytes
+pub fn session_key(secret: &BigUint) -> Vec<u8> {
+    sha1::sha1(&secret.to_bytes_be())[..SESSION_KEY_LEN].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rng::XorShift;
+
+    fn toy_group() -> Group {
+        Group::new(BigUint::from_u64(37), BigUint::from_u64(5))
+    }
+
+    #[test]
+    fn toy_exchange() {
+        let group = toy_group();
+        let a = KeyPair::from_private(&group, BigUint::from_u64(6));
+        let b = KeyPair::from_private(&group, BigUint::from_u64(15));
+        assert_eq!(a.public().to_u64(), Some(11));
+        assert_eq!(b.public().to_u64(), Some(29));
+        assert_eq!(a.shared_secret(&group, b.public()).to_u64(), Some(36));
+        assert_eq!(b.shared_secret(&group, a.public()).to_u64(), Some(36));
+    }
+
+    #[test]
+    fn random_keys_agree() {
+        let mut rng = XorShift::new(33);
+        // the real groups are slow without optimizations, so only try one of them
+        for &(ref group, tries) in &[(toy_group(), 20), (Group::modp_1536(), 1)] {
+            for _ in 0..tries {
+                let (a, b) = (group.keypair(&mut rng), group.keypair(&mut rng));
+                assert!(!a.private().is_zero() && a.private() < &group.p);
+                let secret = a.shared_secret(group, b.public());
+                assert_eq!(secret, b.shared_secret(group, a.public()));
+            }
+        }
+    }
+
+    #[test]
+    fn modp_groups() {
+        for &(ref group, bits) in &[(Group::modp_1536(), 1536), (Group::modp_2048(), 2048), (Group::modp_3072(), 3072)] {
+            assert_eq!(group.p.bits(), bits);
+            assert_eq!(group.element_len(), bits / 8);
+            assert_eq!(group.g.to_u64(), Some(2));
+        }
+        // the challenge's "NIST prime" is group 5; check it's at least a Fermat
+        // pseudoprime to base 2
+        let group = Group::modp_1536();
+        let p_minus_one = &group.p - &BigUint::one();
+        assert_eq!(group.g.modpow(&p_minus_one, &group.p), BigUint::one());
+    }
+
+    #[test]
+    fn session_keys() {
+        let key = session_key(&BigUint::from_u64(36));
+        assert_eq!(key.len(), SESSION_KEY_LEN);
+        assert_eq!(key, sha1::sha1(&[36])[..16].to_vec());
+    }
+}
@@ -0,0 +1,423 @@
+// Man-in-the-middle attacks on unauthenticated Diffie-Hellman (cryptopals
+// challenges 34 and 35).
+// Two endpoints agree on a group, swap public keys and then talk over AES-CBC
+// with a key derived from the shared secret. Everything they send goes through a
+// `Channel`, and every message on the channel passes through a `Node` that can
+// rewrite it. Since neither side authenticates anything, a node that rewrites
+// the public keys or the group's generator can force the shared secret to a
+// value it knows, and then read every message while passing it along untouched,
+// so that neither side notices a thing.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use aes::{self, Aes};
+use bignum::BigUint;
+use dh::{self, Group};
+use modes;
+use padding::{self, PaddingError};
+use rng::RandomSource;
+
+/// which way a message is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    AliceToBob,
+    BobToAlice,
+}
+
+/// everything that gets sent over the channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// the group to use: proposed by Alice, then echoed back by Bob to accept it
+    Group(Group),
+    /// a public key
+    PublicKey(BigUint),
+    /// an encrypted message: AES-CBC ciphertext followed by the IV
+    Data(Vec<u8>),
+}
+
+/// something sitting on the wire between the two endpoints
+pub trait Node {
+    /// sees <message> on its way in <direction>, and returns what gets delivered
+    /// in its place
+    fn relay(&mut self, direction: Direction, message: Message) -> Message;
+}
+
+/// an honest wire, which delivers everything as it is
+pub struct Wire;
+
+impl Node for Wire {
+    fn relay(&mut self, _direction: Direction, message: Message) -> Message {
+        message
+    }
+}
+
+/// a pair of message queues, one each way, with a `Node` in the middle
+pub struct Channel<N: Node> {
+    node: N,
+    to_bob: VecDeque<Message>,
+    to_alice: VecDeque<Message>,
+}
+
+impl<N: Node> Channel<N> {
+    pub fn new(node: N) -> Channel<N> {
+        Channel { node: node, to_bob: VecDeque::new(), to_alice: VecDeque::new() }
+    }
+
+    /// the node in the middle, to see what it's picked up
+    pub fn node(&self) -> &N {
+        &self.node
+    }
+
+    /// sends <message> in <direction>, by way of the node
+    pub fn send(&mut self, direction: Direction, message: Message) {
+        let message = self.node.relay(direction, message);
+        match direction {
+            Direction::AliceToBob => self.to_bob.push_back(message),
+            Direction::BobToAlice => self.to_alice.push_back(message),
+        }
+    }
+
+    /// takes the next message waiting to be delivered in <direction>
+    pub fn receive(&mut self, direction: Direction) -> Option<Message> {
+        match direction {
+            Direction::AliceToBob => self.to_bob.pop_front(),
+            Direction::BobToAlice => self.to_alice.pop_front(),
+        }
+    }
+}
+
+/// everything that can go wrong for an endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// nothing was waiting on the channel
+    NoMessage,
+    /// a message turned up that doesn't belong at this point in the protocol
+    Unexpected { expected: &'static str, found: Message },
+    /// a data message was too short to hold an IV and a block of ciphertext
+    TooShort(usize),
+    /// a message didn't decrypt to properly padded plaintext
+    BadPadding(PaddingError),
+    /// Bob's echo of a message decrypted to something else
+    EchoMismatch,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtocolError::NoMessage =>
+                write!(f, "no message waiting"),
+            ProtocolError::Unexpected { expected, ref found } =>
+                write!(f, "expected {}, got {:?}", expected, found),
+            ProtocolError::TooShort(len) =>
+                write!(f, "data message is only {} bytes, too short for an IV and a block of ciphertext", len),
+            ProtocolError::BadPadding(ref e) =>
+                write!(f, "bad padding: {}", e),
+            ProtocolError::EchoMismatch =>
+                write!(f, "echoed message doesn't match what was sent"),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+/// from challenge 5-34
+/// encrypts <plaintext> with AES-CBC under <key> and a random IV, and appends the
+/// IV
+pub fn encrypt_message<R: RandomSource>(key: &[u8], plaintext: &[u8], rng: &mut R) -> Vec<u8> {
+    let iv = rng.gen_bytes(aes::BLOCK_SIZE);
+    let mut data = modes::cbc_encrypt(&Aes::new(key), &iv, &padding::pkcs7_pad(plaintext, aes::BLOCK_SIZE));
+    data.extend(iv);
+    data
+}
+
+/// undoes `encrypt_message`
+pub fn decrypt_message(key: &[u8], data: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+    let bs = aes::BLOCK_SIZE;
+    if data.len() % bs != 0 {
+        return Err(ProtocolError::BadPadding(PaddingError::NotBlockAligned { len: data.len(), block_size: bs }));
+    }
+    if data.len() < 2 * bs {
+        return Err(ProtocolError::TooShort(data.len()));
+    }
+    let (ciphertext, iv) = data.split_at(data.len() - bs);
+    padding::pkcs7_unpad(&modes::cbc_decrypt(&Aes::new(key), iv, ciphertext), bs).map_err(ProtocolError::BadPadding)
+}
+
+fn receive_group<N: Node>(channel: &mut Channel<N>, direction: Direction) -> Result<Group, ProtocolError> {
+    match channel.receive(direction) {
+        Some(Message::Group(group)) => Ok(group),
+        Some(found) => Err(ProtocolError::Unexpected { expected: "a group", found: found }),
+        None => Err(ProtocolError::NoMessage),
+    }
+}
+
+fn receive_public_key<N: Node>(channel: &mut Channel<N>, direction: Direction) -> Result<BigUint, ProtocolError> {
+    match channel.receive(direction) {
+        Some(Message::PublicKey(key)) => Ok(key),
+        Some(found) => Err(ProtocolError::Unexpected { expected: "a public key", found: found }),
+        None => Err(ProtocolError::NoMessage),
+    }
+}
+
+fn receive_data<N: Node>(channel: &mut Channel<N>, direction: Direction, key: &[u8])
+                         -> Result<Vec<u8>, ProtocolError> {
+    match channel.receive(direction) {
+        Some(Message::Data(data)) => decrypt_message(key, &data),
+        Some(found) => Err(ProtocolError::Unexpected { expected: "data", found: found }),
+        None => Err(ProtocolError::NoMessage),
+    }
+}
+
+/// runs the whole protocol over <channel>: Alice proposes <group>, Bob accepts
+/// it, they swap public keys, and then Alice sends each of <messages>, which Bob
+/// decrypts and echoes back under a fresh IV. Each side only goes on what it
+/// actually receives.
+/// returns the messages as Bob read them
+pub fn run_exchange<N, R>(channel: &mut Channel<N>, group: &Group, messages: &[&[u8]], rng: &mut R)
+                          -> Result<Vec<Vec<u8>>, ProtocolError>
+    where N: Node, R: RandomSource {
+    use self::Direction::*;
+
+    channel.send(AliceToBob, Message::Group(group.clone()));
+    let bob_group = receive_group(channel, AliceToBob)?;
+    channel.send(BobToAlice, Message::Group(bob_group.clone()));
+    let alice_group = receive_group(channel, BobToAlice)?;
+
+    let alice = alice_group.keypair(rng);
+    channel.send(AliceToBob, Message::PublicKey(alice.public().clone()));
+    let bob = bob_group.keypair(rng);
+    let alice_public = receive_public_key(channel, AliceToBob)?;
+    channel.send(BobToAlice, Message::PublicKey(bob.public().clone()));
+    let bob_public = receive_public_key(channel, BobToAlice)?;
+
+    let alice_key = dh::session_key(&alice.shared_secret(&alice_group, &bob_public));
+    let bob_key = dh::session_key(&bob.shared_secret(&bob_group, &alice_public));
+
+    let mut received = Vec::with_capacity(messages.len());
+    for message in messages {
+        channel.send(AliceToBob, Message::Data(encrypt_message(&alice_key, message, rng)));
+        let plaintext = receive_data(channel, AliceToBob, &bob_key)?;
+        channel.send(BobToAlice, Message::Data(encrypt_message(&bob_key, &plaintext, rng)));
+        if receive_data(channel, BobToAlice, &alice_key)? != *message {
+            return Err(ProtocolError::EchoMismatch);
+        }
+        received.push(plaintext);
+    }
+    Ok(received)
+}
+
+/// what an attacker has learned from the messages it's seen
+#[derive(Debug, Clone, Default)]
+pub struct Intercepted {
+    /// the session key, once it's known
+    pub key: Option<Vec<u8>>,
+    /// every message decrypted so far, in both directions
+    pub messages: Vec<(Direction, Vec<u8>)>,
+}
+
+impl Intercepted {
+    /// reads <message> if it's data and we know the key
+    fn snoop(&mut self, direction: Direction, message: &Message) {
+        if let (&Message::Data(ref data), Some(ref key)) = (message, &self.key) {
+            if let Ok(plaintext) = decrypt_message(key, data) {
+                self.messages.push((direction, plaintext));
+            }
+        }
+    }
+}
+
+/// from challenge 5-34
+/// swaps both public keys for p, so both sides compute p^x mod p = 0 as the
+/// shared secret
+#[derive(Debug, Default)]
+pub struct PublicKeyInjector {
+    p: Option<BigUint>,
+    pub intercepted: Intercepted,
+}
+
+impl PublicKeyInjector {
+    pub fn new() -> PublicKeyInjector {
+        PublicKeyInjector::default()
+    }
+}
+
+impl Node for PublicKeyInjector {
+    fn relay(&mut self, direction: Direction, message: Message) -> Message {
+        match message {
+            Message::Group(ref group) => self.p = Some(group.p.clone()),
+            Message::PublicKey(_) => {
+                if let Some(ref p) = self.p {
+                    self.intercepted.key = Some(dh::session_key(&BigUint::zero()));
+                    return Message::PublicKey(p.clone());
+                }
+            }
+            Message::Data(_) => self.intercepted.snoop(direction, &message),
+        }
+        message
+    }
+}
+
+/// the generators an attacker can swap in (challenge 5-35)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaliciousGenerator {
+    /// every power of 1 is 1
+    One,
+    /// every power of p is 0 mod p
+    P,
+    /// every power of p-1 is 1 or p-1 mod p, depending on whether the exponent
+    /// is even or odd
+    PMinusOne,
+}
+
+impl MaliciousGenerator {
+    fn value(&self, p: &BigUint) -> BigUint {
+        match *self {
+            MaliciousGenerator::One => BigUint::one(),
+            MaliciousGenerator::P => p.clone(),
+            MaliciousGenerator::PMinusOne => p - &BigUint::one(),
+        }
+    }
+}
+
+/// from challenge 5-35
+/// swaps the generator in the group both sides agree on, which leaves only one
+/// or two possible shared secrets, and works out which one it is from the
+/// public keys going past
+#[derive(Debug)]
+pub struct GeneratorInjector {
+    generator: MaliciousGenerator,
+    p: Option<BigUint>,
+    /// the public keys seen so far
+    public_keys: Vec<BigUint>,
+    pub intercepted: Intercepted,
+}
+
+impl GeneratorInjector {
+    pub fn new(generator: MaliciousGenerator) -> GeneratorInjector {
+        GeneratorInjector { generator: generator, p: None, public_keys: Vec::new(),
+                            intercepted: Intercepted::default() }
+    }
+
+    /// the shared secret, given both public keys
+    fn shared_secret(&self, p: &BigUint) -> BigUint {
+        match self.generator {
+            MaliciousGenerator::One => BigUint::one(),
+            MaliciousGenerator::P => BigUint::zero(),
+            // (p-1)^ab is p-1 only if ab is odd, which means both a and b are,
+            // which means both public keys are p-1 too
+            MaliciousGenerator::PMinusOne => {
+                let minus_one = p - &BigUint::one();
+                if self.public_keys.iter().all(|key| *key == minus_one) {
+                    minus_one
+                } else {
+                    BigUint::one()
+                }
+            }
+        }
+    }
+}
+
+impl Node for GeneratorInjector {
+    fn relay(&mut self, direction: Direction, message: Message) -> Message {
+        match message {
+            Message::Group(group) => {
+                let g = self.generator.value(&group.p);
+                self.p = Some(group.p.clone());
+                return Message::Group(Group::new(group.p, g));
+            }
+            Message::PublicKey(ref key) => {
+                self.public_keys.push(key.clone());
+                if self.public_keys.len() == 2 {
+                    if let Some(p) = self.p.clone() {
+                        self.intercepted.key = Some(dh::session_key(&self.shared_secret(&p)));
+                    }
+                }
+            }
+            Message::Data(_) => self.intercepted.snoop(direction, &message),
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rng::XorShift;
+
+    const MESSAGES: &'static [&'static [u8]] = &[b"", b"hello bob", b"a message that takes up more than one block"];
+
+    fn toy_group() -> Group {
+        Group::new(BigUint::from_u64(37), BigUint::from_u64(5))
+    }
+
+    fn sent() -> Vec<Vec<u8>> {
+        MESSAGES.iter().map(|m| m.to_vec()).collect()
+    }
+
+    /// every message in both directions, in the order the attacker should see them
+    fn both_ways() -> Vec<(Direction, Vec<u8>)> {
+        MESSAGES.iter()
+            .flat_map(|m| vec![(Direction::AliceToBob, m.to_vec()), (Direction::BobToAlice, m.to_vec())])
+            .collect()
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let mut rng = XorShift::new(34);
+        let key = rng.gen_bytes(16);
+        for message in MESSAGES {
+            let data = encrypt_message(&key, message, &mut rng);
+            assert_eq!(data.len() % aes::BLOCK_SIZE, 0);
+            assert_eq!(decrypt_message(&key, &data).unwrap(), *message);
+        }
+        for &len in &[0, 16] {
+            assert_eq!(decrypt_message(&key, &vec![0; len]), Err(ProtocolError::TooShort(len)));
+        }
+        for &len in &[15, 31, 33] {
+            let expected = PaddingError::NotBlockAligned { len: len, block_size: aes::BLOCK_SIZE };
+            assert_eq!(decrypt_message(&key, &vec![0; len]), Err(ProtocolError::BadPadding(expected)));
+        }
+    }
+
+    #[test]
+    fn honest_wire() {
+        let mut rng = XorShift::new(34);
+        for group in &[toy_group(), Group::modp_1536()] {
+            let mut channel = Channel::new(Wire);
+            assert_eq!(run_exchange(&mut channel, group, MESSAGES, &mut rng), Ok(sent()));
+            assert_eq!(channel.receive(Direction::AliceToBob), None);
+            assert_eq!(channel.receive(Direction::BobToAlice), None);
+        }
+    }
+
+    #[test]
+    fn public_key_injection() {
+        let mut rng = XorShift::new(34);
+        let mut channel = Channel::new(PublicKeyInjector::new());
+        assert_eq!(run_exchange(&mut channel, &Group::modp_1536(), MESSAGES, &mut rng), Ok(sent()));
+        let intercepted = &channel.node().intercepted;
+        assert_eq!(intercepted.key, Some(dh::session_key(&BigUint::zero())));
+        assert_eq!(intercepted.messages, both_ways());
+    }
+
+    #[test]
+    fn generator_injection() {
+        let group = toy_group();
+        let minus_one = dh::session_key(&(&group.p - &BigUint::one()));
+        for &generator in &[MaliciousGenerator::One, MaliciousGenerator::P, MaliciousGenerator::PMinusOne] {
+            // with p-1 the secret depends on the parity of both private keys, so
+            // run enough exchanges to see it come out both ways
+            let mut saw_minus_one = false;
+            for seed in 1..21 {
+                let mut rng = XorShift::new(seed);
+                let mut channel = Channel::new(GeneratorInjector::new(generator));
+                assert_eq!(run_exchange(&mut channel, &group, MESSAGES, &mut rng), Ok(sent()));
+                let intercepted = &channel.node().intercepted;
+                assert_eq!(intercepted.messages, both_ways());
+                saw_minus_one |= intercepted.key.as_ref() == Some(&minus_one);
+            }
+            assert_eq!(saw_minus_one, generator == MaliciousGenerator::PMinusOne);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// Byte-at-a-time ECB decryption (cryptopals challenges 12 and 14).
+// Given an oracle that encrypts prefix || our input || secret under ECB, we can
+// line things up so that exactly one unknown byte of the secret lands at the end
+// of a block we otherwise control. Encrypting all 256 possibilities for that
+// block and matching them against the oracle's output gives the byte away, and
+// then we shift everything over by one and do it again.
+
+use std::collections::HashMap;
+use ecb_detect;
+use oracle::Oracle;
+
+/// the largest block size we bother looking for
+const MAX_BLOCK_SIZE: usize = 64;
+
+/// everything we learn about the oracle before we start decrypting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcbOracleProfile {
+    pub block_size: usize,
+    /// the number of bytes the oracle puts in front of our input
+    pub prefix_len: usize,
+    /// the number of bytes the oracle puts after our input (what we're after)
+    pub suffix_len: usize,
+}
+
+/// finds the block size by feeding the oracle longer and longer inputs until the
+/// ciphertext grows. Returns (block size, total length of the prefix and suffix).
+pub fn detect_block_size<O: Oracle>(oracle: &mut O) -> (usize, usize) {
+    let base_len = oracle.encrypt(&[]).len();
+    for n in 1..MAX_BLOCK_SIZE + 1 {
+        let len = oracle.encrypt(&vec![0; n]).len();
+        if len > base_len {
+            // n bytes of input exactly filled the last block, so the padding
+            // spilled over into a whole new one
+            return (len - base_len, base_len - n);
+        }
+    }
+    panic!("couldn't find a block size up to {} bytes", MAX_BLOCK_SIZE);
+}
+
+/// returns true if the oracle encrypts with ECB
+pub fn confirm_ecb<O: Oracle>(oracle: &mut O, block_size: usize) -> bool {
+    // three blocks' worth guarantees two whole identical blocks whatever the
+    // prefix length is
+    ecb_detect::is_ecb(&oracle.encrypt(&vec![0; 3 * block_size]), block_size)
+}
+
+/// encrypts <spacer_len> spacer bytes followed by two blocks of <filler>, and
+/// splits the ciphertext into blocks
+fn encrypt_filler_blocks<O: Oracle>(oracle: &mut O, block_size: usize, spacer: u8, spacer_len: usize,
+                                    filler: u8) -> Vec<Vec<u8>> {
+    let mut input = vec![spacer; spacer_len];
+    input.extend(vec![filler; 2 * block_size]);
+    oracle.encrypt(&input).chunks(block_size).map(|block| block.to_vec()).collect()
+}
+
+/// finds the number of bytes the oracle puts in front of our input, assuming
+/// <filler> doesn't show up at the end of the prefix
+fn prefix_len_with<O: Oracle>(oracle: &mut O, block_size: usize, filler: u8) -> usize {
+    // two identical blocks of filler, pushed along by 0 to block_size-1 bytes of
+    // something else. As soon as they line up with the block boundaries, they'll
+    // encrypt to two identical adjacent blocks, and we know where we are.
+    // Not every pair of identical blocks is ours, though: the prefix or suffix can
+    // repeat blocks of their own, and a suffix that starts with filler bytes
+    // lengthens our run so that it lines up too early. So each match gets checked
+    // again with a different filler byte. Only our own blocks change with it (and
+    // stay identical to each other).
+    let spacer = filler.wrapping_add(1);
+    let other = filler ^ 0x80;
+    for i in 0..block_size {
+        let blocks = encrypt_filler_blocks(oracle, block_size, spacer, i, filler);
+        let check = encrypt_filler_blocks(oracle, block_size, spacer, i, other);
+        let ours = (0..blocks.len() - 1).find(|&j| {
+            j * block_size >= i &&
+            blocks[j] == blocks[j + 1] &&
+            check[j] == check[j + 1] &&
+            blocks[j] != check[j]
+        });
+        if let Some(j) = ours {
+            return j * block_size - i;
+        }
+    }
+    panic!("couldn't line up two identical blocks; is this really ECB?");
+}
+
+/// finds the number of bytes the oracle puts in front of our input
+pub fn detect_prefix_len<O: Oracle>(oracle: &mut O, block_size: usize) -> usize {
+    // if the prefix happens to end in a few filler bytes, they merge with ours and
+    // the blocks line up early, making the prefix look shorter than it is. It
+    // can't end in two different bytes at once, so try two fillers and believe
+    // the longer answer.
+    let a = prefix_len_with(oracle, block_size, 0x00);
+    let b = prefix_len_with(oracle, block_size, 0xff);
+    if a > b { a } else { b }
+}
+
+/// works out the block size, prefix length and suffix length of an oracle
+/// returns None if the oracle isn't using ECB
+pub fn profile<O: Oracle>(oracle: &mut O) -> Option<EcbOracleProfile> {
+    let (block_size, affix_len) = detect_block_size(oracle);
+    if !confirm_ecb(oracle, block_size) {
+        return None;
+    }
+    let prefix_len = detect_prefix_len(oracle, block_size);
+    Some(EcbOracleProfile {
+        block_size: block_size,
+        prefix_len: prefix_len,
+        suffix_len: affix_len - prefix_len,
+    })
+}
+
+/// decrypts the suffix one byte at a time
+/// after every byte, <progress> is called with what's been recovered so far and
+/// the total length of the suffix
+pub fn recover_suffix<O, F>(oracle: &mut O, profile: &EcbOracleProfile, mut progress: F) -> Vec<u8>
+    where O: Oracle, F: FnMut(&[u8], usize) {
+    let bs = profile.block_size;
+    // enough filler to round the prefix up to a whole block; from then on,
+    // everything is block-aligned as if there were no prefix at all
+    let align = (bs - profile.prefix_len % bs) % bs;
+    let skip_blocks = (profile.prefix_len + align) / bs;
+
+    let mut recovered: Vec<u8> = Vec::with_capacity(profile.suffix_len);
+    while recovered.len() < profile.suffix_len {
+        let k = recovered.len();
+        // push the suffix along so that byte k is the last byte of a block
+        let filler_len = bs - 1 - k % bs;
+        let target_block = skip_blocks + k / bs;
+        let ciphertext = oracle.encrypt(&vec![0; align + filler_len]);
+        let target = &ciphertext[target_block * bs..(target_block + 1) * bs];
+
+        // the block_size-1 bytes we already know which come right before byte k
+        // (still filler, at the start)
+        let mut known: Vec<u8> = vec![0; align + bs - 1];
+        let start = if k + 1 >= bs { k + 1 - bs } else { 0 };
+        let tail = &recovered[start..];
+        let known_len = known.len();
+        known[known_len - tail.len()..].copy_from_slice(tail);
+
+        // encrypt every possible last byte in one go by stacking the candidate
+        // blocks up, then look ours up
+        let mut input = Vec::with_capacity(align + 256 * bs);
+        input.extend(vec![0; align]);
+        for b in 0..256 {
+            input.extend_from_slice(&known[align..]);
+            input.push(b as u8);
+        }
+        let dictionary_ct = oracle.encrypt(&input);
+        let dictionary: HashMap<&[u8], u8> = (0..256)
+            .map(|b| {
+                let i = skip_blocks + b;
+                (&dictionary_ct[i * bs..(i + 1) * bs], b as u8)
+            })
+            .collect();
+
+        match dictionary.get(target) {
+            Some(&b) => recovered.push(b),
+            None => panic!("no match for byte {} of the suffix", k),
+        }
+        progress(&recovered, profile.suffix_len);
+    }
+    recovered
+}
+
+/// profiles the oracle and decrypts the whole suffix
+/// returns None if the oracle isn't using ECB
+pub fn byte_at_a_time<O: Oracle>(oracle: &mut O) -> Option<Vec<u8>> {
+    let profile = profile(oracle)?;
+    Some(recover_suffix(oracle, &profile, |_, _| ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::{self, Aes};
+    use modes;
+    use oracle::FnOracle;
+    use padding;
+
+    /// finds the prefix length for an oracle with a fixed <prefix> and <suffix>
+    fn detect(prefix: &[u8], suffix: &[u8]) -> usize {
+        let cipher = Aes::new(b"YELLOW SUBMARINE");
+        let mut oracle = FnOracle::new().with_encrypt(|input| {
+            let mut buf = prefix.to_vec();
+            buf.extend_from_slice(input);
+            buf.extend_from_slice(suffix);
+            modes::ecb_encrypt(&cipher, &padding::pkcs7_pad(&buf, aes::BLOCK_SIZE))
+        });
+        detect_prefix_len(&mut oracle, aes::BLOCK_SIZE)
+    }
+
+    #[test]
+    fn prefix_lengths() {
+        for len in 0..40 {
+            assert_eq!(detect(&vec![b'p'; len], b"secret suffix"), len);
+        }
+    }
+
+    #[test]
+    fn suffix_starting_with_filler() {
+        for len in 0..20 {
+            for &filler in &[0x00, 0xff] {
+                let mut suffix = vec![filler; 20];
+                suffix.extend_from_slice(b"rest of the suffix");
+                assert_eq!(detect(&vec![b'p'; len], &suffix), len);
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_ending_in_filler() {
+        for len in 1..20 {
+            assert_eq!(detect(&vec![0x00; len], b"suffix"), len);
+            assert_eq!(detect(&vec![0xff; len], b"suffix"), len);
+        }
+    }
+
+    #[test]
+    fn repeated_blocks_in_affixes() {
+        // the prefix and suffix both contain identical adjacent blocks
+        let prefix = vec![b'a'; 37];
+        assert_eq!(detect(&prefix, &[b'z'; 48]), 37);
+        assert_eq!(detect(&[b'a'; 32], &[b'z'; 48]), 32);
+    }
+
+    #[test]
+    fn recovers_suffix() {
+        let suffix = b"\x00\x00\x00Rollin' in my 5.0, with my rag-top down so my hair can blow";
+        let cipher = Aes::new(b"YELLOW SUBMARINE");
+        let mut oracle = FnOracle::new().with_encrypt(|input| {
+            let mut buf = b"random prefix".to_vec();
+            buf.extend_from_slice(input);
+            buf.extend_from_slice(suffix);
+            modes::ecb_encrypt(&cipher, &padding::pkcs7_pad(&buf, aes::BLOCK_SIZE))
+        });
+        assert_eq!(byte_at_a_time(&mut oracle), Some(suffix.to_vec()));
+    }
+}
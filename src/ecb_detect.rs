@@ -0,0 +1,99 @@
+// Spotting ECB mode. ECB encrypts equal plaintext blocks to equal ciphertext
+// blocks, so any ciphertext with repeated blocks was almost certainly made with
+// ECB (for any other mode, or for random data, a repeated 16-byte block is
+// astronomically unlikely).
+
+use std::collections::HashSet;
+
+/// the block sizes to try when the block size isn't known: DES/3DES (8 bytes),
+/// AES (16 bytes) and the occasional 32-byte block cipher
+pub const COMMON_BLOCK_SIZES: [usize; 3] = [8, 16, 32];
+
+/// returns the number of <block_size>-byte blocks in <buf> that are copies of an
+/// earlier block (a trailing partial block is ignored)
+pub fn duplicate_blocks(buf: &[u8], block_size: usize) -> usize {
+    assert!(block_size > 0);
+    let mut seen = HashSet::new();
+    buf.chunks(block_size)
+       .filter(|block| block.len() == block_size)
+       .filter(|block| !seen.insert(*block))
+       .count()
+}
+
+/// returns true if <buf> has any repeated <block_size>-byte blocks
+pub fn is_ecb(buf: &[u8], block_size: usize) -> bool {
+    duplicate_blocks(buf, block_size) > 0
+}
+
+/// scores <buf> with every block size in <block_sizes>, and returns
+/// (block size, number of duplicate blocks) for the best one
+/// block sizes are compared by the number of duplicated bytes rather than
+/// blocks, since a repeated 16-byte block is also two repeated 8-byte blocks.
+/// On a tie, the larger block size wins for the same reason.
+pub fn ecb_score(buf: &[u8], block_sizes: &[usize]) -> (usize, usize) {
+    let mut best = (0, 0);
+    for &block_size in block_sizes {
+        let duplicates = duplicate_blocks(buf, block_size);
+        if duplicates > 0 && (duplicates * block_size, block_size) > (best.1 * best.0, best.0) {
+            best = (block_size, duplicates);
+        }
+    }
+    best
+}
+
+/// scores every buffer in <bufs> with `ecb_score`, and returns
+/// (index into bufs, block size, number of duplicate blocks) for each one, most
+/// ECB-looking first
+pub fn rank_ecb<T: AsRef<[u8]>>(bufs: &[T], block_sizes: &[usize]) -> Vec<(usize, usize, usize)> {
+    let mut v: Vec<(usize, usize, usize)> = bufs.iter().enumerate().map(|(i, buf)| {
+        let (block_size, duplicates) = ecb_score(buf.as_ref(), block_sizes);
+        (i, block_size, duplicates)
+    }).collect();
+    v.sort_by(|a, b| (b.2 * b.1).cmp(&(a.2 * a.1)).then(a.0.cmp(&b.0)));
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::Aes;
+    use modes;
+
+    #[test]
+    fn counts_duplicates() {
+        assert_eq!(duplicate_blocks(b"", 16), 0);
+        assert_eq!(duplicate_blocks(b"aaaabbbbaaaacccc", 4), 1);
+        assert_eq!(duplicate_blocks(b"aaaaaaaaaaaabbbb", 4), 2);
+        // the trailing partial block doesn't count, even if it matches
+        assert_eq!(duplicate_blocks(b"aaaabbbbaa", 4), 0);
+        assert_eq!(duplicate_blocks(b"aaaabbbbaaaa", 5), 0);
+        assert!(is_ecb(b"aaaabbbbaaaa", 4) && !is_ecb(b"aaaabbbbaaaa", 5));
+    }
+
+    #[test]
+    fn scores_by_duplicated_bytes() {
+        assert_eq!(ecb_score(b"no repeats here", &COMMON_BLOCK_SIZES), (0, 0));
+        // a repeated 16-byte block is also two repeated 8-byte blocks, which is a
+        // tie, and the larger block size wins it whichever order they're tried in
+        let buf = b"aaaaaaaabbbbbbbbaaaaaaaabbbbbbbb";
+        assert_eq!(ecb_score(buf, &[8, 16, 32]), (16, 1));
+        assert_eq!(ecb_score(buf, &[32, 16, 8]), (16, 1));
+        // but a repeated 8-byte block on its own is only an 8-byte block
+        assert_eq!(ecb_score(b"aaaaaaaaaaaaaaaabbbbbbbbcccccccc", &COMMON_BLOCK_SIZES), (8, 1));
+        assert_eq!(ecb_score(b"aaaaaaaaaaaaaaaaaaaaaaaa", &COMMON_BLOCK_SIZES), (8, 2));
+    }
+
+    #[test]
+    fn ranks_ecb_first() {
+        let cipher = Aes::new(b"YELLOW SUBMARINE");
+        let plaintext = &[&b"sixteen byte msg"[..], b"sixteen byte msg", b"something else!!", b"sixteen byte msg"].concat();
+        let iv = [7; 16];
+        let bufs = vec![
+            modes::cbc_encrypt(&cipher, &iv, plaintext),
+            modes::ctr_encrypt(&cipher, 0, &[0; 64]),
+            modes::ecb_encrypt(&cipher, plaintext),
+            modes::cbc_encrypt(&cipher, &iv, &[0; 64]),
+        ];
+        assert_eq!(rank_ecb(&bufs, &COMMON_BLOCK_SIZES), vec![(2, 16, 2), (0, 0, 0), (1, 0, 0), (3, 0, 0)]);
+    }
+}
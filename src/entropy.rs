@@ -0,0 +1,281 @@
+// Statistical tests for figuring out what kind of data a buffer holds.
+// None of these are conclusive on their own, but together they're a decent
+// first pass at telling plaintext apart from compressed or encrypted data
+// before we start throwing the XOR breakers at it.
+
+use std::ops::Range;
+
+/// what a buffer (or a region of a buffer) most likely contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    /// low-entropy, structured data: text, code, or text under a short
+    /// repeating-key XOR (which doesn't change the entropy much)
+    Plain,
+    /// high entropy, but with byte frequencies that are still noticeably skewed
+    Compressed,
+    /// high entropy and indistinguishable from uniform noise
+    Encrypted,
+}
+
+/// all the test results for a single buffer
+#[derive(Debug, Clone, Copy)]
+pub struct RandomnessReport {
+    /// shannon entropy in bits per byte (0.0 to 8.0)
+    pub entropy: f64,
+    /// chi-square statistic of the byte histogram against a uniform distribution
+    pub chi_square: f64,
+    /// correlation between each byte and the next one (-1.0 to 1.0)
+    pub serial_correlation: f64,
+    /// z-score of the number of runs of identical bits
+    pub runs_z: f64,
+}
+
+impl RandomnessReport {
+    /// returns true if every test is consistent with uniformly random bytes
+    pub fn looks_random(&self) -> bool {
+        self.entropy > 7.5 &&
+        chi_square_z(self.chi_square, 255.0).abs() < 3.0 &&
+        self.serial_correlation.abs() < 0.05 &&
+        self.runs_z.abs() < 3.0
+    }
+
+    /// best guess at what kind of data produced this report
+    pub fn classify(&self) -> DataKind {
+        if self.looks_random() {
+            DataKind::Encrypted
+        } else if self.entropy > 6.5 {
+            DataKind::Compressed
+        } else {
+            DataKind::Plain
+        }
+    }
+}
+
+/// given a buffer, returns an array where the value at index i is the number of
+/// times the byte i appears in the buffer
+pub fn byte_histogram(buf: &[u8]) -> [u64; 256] {
+    let mut counts = [0u64; 256];
+    for &b in buf {
+        counts[b as usize] += 1;
+    }
+    counts
+}
+
+/// computes the shannon entropy of a buffer in bits per byte
+/// english text usually lands somewhere around 4.5, while compressed and
+/// encrypted data are both very close to the maximum of 8.0
+pub fn shannon_entropy(buf: &[u8]) -> f64 {
+    if buf.is_empty() {
+        return 0.0;
+    }
+    let len = buf.len() as f64;
+    byte_histogram(buf).iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// computes the shannon entropy of every <window>-byte window of the buffer,
+/// moving <step> bytes at a time
+/// returns a vector of (offset of the window, entropy of the window)
+/// the last window is dropped if it would run off the end of the buffer, unless
+/// the buffer is shorter than a single window
+pub fn sliding_window_entropy(buf: &[u8], window: usize, step: usize) -> Vec<(usize, f64)> {
+    assert!(window > 0 && step > 0);
+    if buf.len() <= window {
+        return vec![(0, shannon_entropy(buf))];
+    }
+    let mut v = vec![];
+    let mut offset = 0;
+    while offset + window <= buf.len() {
+        v.push((offset, shannon_entropy(&buf[offset..offset+window])));
+        offset += step;
+    }
+    v
+}
+
+/// computes the chi-square statistic of the byte histogram of the buffer
+/// against a uniform distribution over all 256 byte values
+/// for random data this should be close to 255 (the degrees of freedom); use
+/// `chi_square_z` to turn it into something easier to threshold
+/// note that this test needs a few thousand bytes to mean anything
+pub fn chi_square_uniformity(buf: &[u8]) -> f64 {
+    if buf.is_empty() {
+        return 0.0;
+    }
+    let expected = buf.len() as f64 / 256.0;
+    byte_histogram(buf).iter()
+        .map(|&count| {
+            let diff = count as f64 - expected;
+            diff * diff / expected
+        })
+        .sum()
+}
+
+/// converts a chi-square statistic with <dof> degrees of freedom to an
+/// approximate standard normal z-score using the wilson-hilferty transform
+pub fn chi_square_z(chi_square: f64, dof: f64) -> f64 {
+    let v = 2.0 / (9.0 * dof);
+    ((chi_square / dof).powf(1.0 / 3.0) - (1.0 - v)) / v.sqrt()
+}
+
+/// computes the serial correlation coefficient of the buffer, i.e. how well each
+/// byte predicts the next one (wrapping around at the end)
+/// random data gives something close to 0.0; text tends to be well above that
+pub fn serial_correlation(buf: &[u8]) -> f64 {
+    let n = buf.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let (mut sum, mut sum_sq, mut sum_prod) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let x = buf[i] as f64;
+        let y = buf[(i + 1) % n] as f64;
+        sum += x;
+        sum_sq += x * x;
+        sum_prod += x * y;
+    }
+    let n = n as f64;
+    let denominator = n * sum_sq - sum * sum;
+    if denominator == 0.0 {
+        // every byte is the same, so the correlation is undefined. call it
+        // perfectly correlated since that's obviously not random
+        return 1.0;
+    }
+    (n * sum_prod - sum * sum) / denominator
+}
+
+/// performs a wald-wolfowitz runs test over the bits of the buffer and returns
+/// the z-score of the observed number of runs
+/// a |z| above ~2 means there are suspiciously many or few runs of identical bits
+pub fn runs_test(buf: &[u8]) -> f64 {
+    let bits = buf.iter().flat_map(|&b| (0..8).rev().map(move |i| (b >> i) & 1));
+    let (mut ones, mut zeros, mut runs) = (0.0f64, 0.0f64, 0.0f64);
+    let mut prev = None;
+    for bit in bits {
+        if bit == 1 { ones += 1.0; } else { zeros += 1.0; }
+        if prev != Some(bit) {
+            runs += 1.0;
+            prev = Some(bit);
+        }
+    }
+    let n = ones + zeros;
+    if ones == 0.0 || zeros == 0.0 {
+        // a single run; about as far from random as it gets
+        return if n == 0.0 { 0.0 } else { -::std::f64::INFINITY };
+    }
+    let expected = 2.0 * ones * zeros / n + 1.0;
+    let variance = (expected - 1.0) * (expected - 2.0) / (n - 1.0);
+    (runs - expected) / variance.sqrt()
+}
+
+/// runs every test above on the buffer
+pub fn analyze(buf: &[u8]) -> RandomnessReport {
+    RandomnessReport {
+        entropy: shannon_entropy(buf),
+        chi_square: chi_square_uniformity(buf),
+        serial_correlation: serial_correlation(buf),
+        runs_z: runs_test(buf),
+    }
+}
+
+/// splits the buffer into <window>-byte chunks, classifies each one, and merges
+/// adjacent chunks of the same kind
+/// returns a vector of (byte range, kind) covering the whole buffer
+/// regions flagged as `Plain` are the ones worth feeding to the XOR breakers
+pub fn flag_regions(buf: &[u8], window: usize) -> Vec<(Range<usize>, DataKind)> {
+    assert!(window > 0);
+    let mut regions: Vec<(Range<usize>, DataKind)> = vec![];
+    for (i, chunk) in buf.chunks(window).enumerate() {
+        let start = i * window;
+        let kind = analyze(chunk).classify();
+        let merged = match regions.last_mut() {
+            Some(&mut (ref mut range, last_kind)) if last_kind == kind => {
+                range.end = start + chunk.len();
+                true
+            },
+            _ => false,
+        };
+        if !merged {
+            regions.push((start..start + chunk.len(), kind));
+        }
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ngram::ENGLISH_CORPUS;
+
+    /// a xorshift64* stream, so the "random" buffers come out the same every run
+    fn random_bytes(seed: u64, n: usize) -> Vec<u8> {
+        let mut state = seed | 1;
+        (0..n).map(|_| {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            (state.wrapping_mul(0x2545f4914f6cdd1d) >> 56) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn entropy_extremes() {
+        assert_eq!(shannon_entropy(&[]), 0.0);
+        assert_eq!(shannon_entropy(&[0; 1000]), 0.0);
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        assert!((shannon_entropy(&all_bytes) - 8.0).abs() < 1e-9);
+        assert!((shannon_entropy(b"abab") - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn random_bytes_look_random() {
+        let random = random_bytes(26, 1 << 16);
+        assert!(chi_square_z(chi_square_uniformity(&random), 255.0).abs() < 3.0);
+        assert!(serial_correlation(&random).abs() < 0.05);
+        assert!(runs_test(&random).abs() < 3.0);
+        let report = analyze(&random);
+        assert!(report.looks_random());
+        assert_eq!(report.classify(), DataKind::Encrypted);
+    }
+
+    #[test]
+    fn english_does_not_look_random() {
+        let text = ENGLISH_CORPUS.as_bytes();
+        assert!(chi_square_z(chi_square_uniformity(text), 255.0) > 3.0);
+        assert!(shannon_entropy(text) < 5.0);
+        let report = analyze(text);
+        assert!(!report.looks_random());
+        assert_eq!(report.classify(), DataKind::Plain);
+    }
+
+    #[test]
+    fn degenerate_buffers() {
+        assert_eq!(serial_correlation(&[7; 100]), 1.0);
+        assert_eq!(runs_test(&[]), 0.0);
+        assert_eq!(runs_test(&[0; 10]), -::std::f64::INFINITY);
+    }
+
+    #[test]
+    fn sliding_windows() {
+        let buf: Vec<u8> = (0..=255).collect();
+        let windows = sliding_window_entropy(&buf, 16, 8);
+        assert_eq!(windows.len(), 31);
+        assert_eq!(windows[1].0, 8);
+        assert!(windows.iter().all(|&(_, e)| (e - 4.0).abs() < 1e-9));
+        assert_eq!(sliding_window_entropy(b"aaaa", 16, 8), vec![(0, 0.0)]);
+    }
+
+    #[test]
+    fn flags_text_between_noise() {
+        let mut buf = random_bytes(126, 8192);
+        buf.extend_from_slice(&ENGLISH_CORPUS.as_bytes()[..8192]);
+        buf.extend(random_bytes(127, 8192));
+        assert_eq!(flag_regions(&buf, 4096), vec![(0..8192, DataKind::Encrypted),
+                                                  (8192..16384, DataKind::Plain),
+                                                  (16384..24576, DataKind::Encrypted)]);
+    }
+}
@@ -0,0 +1,144 @@
+// Breaks CTR when the same nonce (and so the same keystream) was used for several
+// messages (cryptopals challenges 19 and 20).
+// Every ciphertext is its plaintext XOR the same keystream, so byte i of every
+// ciphertext was encrypted with keystream byte i. Lining the ciphertexts up and
+// reading down the columns turns this into a pile of single-byte XOR problems,
+// the same way transposing did for the repeating-key XOR in challenge 6.
+
+use cryptoutil;
+
+/// recovers the keystream shared by a set of ciphertexts, and lets you fix up the
+/// bytes statistics got wrong
+pub struct FixedNonceBreaker {
+    ciphertexts: Vec<Vec<u8>>,
+    keystream: Vec<u8>,
+}
+
+impl FixedNonceBreaker {
+    /// cuts every ciphertext down to the length of the shortest one before solving
+    /// every column is then solved with every ciphertext, which makes for the most
+    /// reliable keystream, but the tails of the longer messages are lost
+    pub fn truncated<T: AsRef<[u8]>>(ciphertexts: &[T]) -> FixedNonceBreaker {
+        let len = ciphertexts.iter().map(|c| c.as_ref().len()).min().unwrap_or(0);
+        let truncated = ciphertexts.iter().map(|c| c.as_ref()[..len].to_vec()).collect();
+        FixedNonceBreaker::solve(truncated)
+    }
+
+    /// keeps every ciphertext whole, solving each column with whichever
+    /// ciphertexts are long enough to reach it
+    /// towards the end of the longest ciphertexts, the columns only have a couple
+    /// of bytes in them and the statistics turn into guesswork, which is what
+    /// `correct` is for (`column_height` tells you how far to trust each byte)
+    pub fn ragged<T: AsRef<[u8]>>(ciphertexts: &[T]) -> FixedNonceBreaker {
+        FixedNonceBreaker::solve(ciphertexts.iter().map(|c| c.as_ref().to_vec()).collect())
+    }
+
+    fn solve(ciphertexts: Vec<Vec<u8>>) -> FixedNonceBreaker {
+        let len = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+        let keystream = (0..len).map(|i| {
+            let column: Vec<u8> = ciphertexts.iter().filter_map(|c| c.get(i).cloned()).collect();
+            cryptoutil::break_single_byte_xor(&column).0
+        }).collect();
+        FixedNonceBreaker { ciphertexts: ciphertexts, keystream: keystream }
+    }
+
+    /// the keystream recovered so far
+    pub fn keystream(&self) -> &[u8] {
+        &self.keystream
+    }
+
+    /// returns the number of ciphertexts that reach <position>, i.e. how many
+    /// bytes keystream byte <position> was solved from
+    pub fn column_height(&self, position: usize) -> usize {
+        self.ciphertexts.iter().filter(|c| c.len() > position).count()
+    }
+
+    /// overrides keystream byte <position>
+    pub fn set_keystream_byte(&mut self, position: usize, value: u8) {
+        self.keystream[position] = value;
+    }
+
+    /// tells the breaker that ciphertext <index> decrypts to <known> starting at
+    /// <offset>, and fixes the keystream to match
+    /// since the keystream is shared, this fixes those bytes in every plaintext,
+    /// so guessing the end of one word usually fixes several lines at once
+    pub fn correct(&mut self, index: usize, offset: usize, known: &[u8]) {
+        let ciphertext = &self.ciphertexts[index];
+        assert!(offset + known.len() <= ciphertext.len(), "correction runs past the end of the ciphertext");
+        let fixed = cryptoutil::xor_bytes(&ciphertext[offset..offset + known.len()], known);
+        self.keystream[offset..offset + known.len()].copy_from_slice(&fixed);
+    }
+
+    /// decrypts ciphertext <index> with the current keystream
+    pub fn plaintext(&self, index: usize) -> Vec<u8> {
+        let ciphertext = &self.ciphertexts[index];
+        cryptoutil::xor_bytes(ciphertext, &self.keystream[..ciphertext.len()])
+    }
+
+    /// decrypts every ciphertext with the current keystream
+    pub fn plaintexts(&self) -> Vec<Vec<u8>> {
+        (0..self.ciphertexts.len()).map(|i| self.plaintext(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::Aes;
+    use modes;
+    use ngram::ENGLISH_CORPUS;
+
+    /// sentences from the corpus, encrypted under one key and nonce
+    fn lines() -> (Vec<&'static [u8]>, Vec<Vec<u8>>) {
+        let lines: Vec<&[u8]> = ENGLISH_CORPUS.split(|c| c == '.' || c == '\n')
+                                              .map(|s| s.trim().as_bytes())
+                                              .filter(|s| s.len() >= 20)
+                                              .take(50)
+                                              .collect();
+        let cipher = Aes::new(b"YELLOW SUBMARINE");
+        let ciphertexts = lines.iter().map(|line| modes::ctr_encrypt(&cipher, 38, line)).collect();
+        (lines, ciphertexts)
+    }
+
+    #[test]
+    fn truncated() {
+        let (lines, ciphertexts) = lines();
+        let mut breaker = FixedNonceBreaker::truncated(&ciphertexts);
+        let shortest = lines.iter().map(|l| l.len()).min().unwrap();
+        assert_eq!(breaker.keystream().len(), shortest);
+        assert!((0..shortest).all(|i| breaker.column_height(i) == lines.len()));
+        // the statistics don't expect every line to start with a capital, but
+        // they get everything else
+        assert!(breaker.plaintexts().iter().zip(&lines).all(|(p, l)| p[1..] == l[1..shortest]));
+        breaker.correct(0, 0, b"I");
+        assert!(breaker.plaintexts().iter().zip(&lines).all(|(p, l)| p[..] == l[..shortest]));
+    }
+
+    #[test]
+    fn ragged() {
+        let (lines, ciphertexts) = lines();
+        let mut breaker = FixedNonceBreaker::ragged(&ciphertexts);
+        let longest = (0..lines.len()).max_by_key(|&i| lines[i].len()).unwrap();
+        let len = lines[longest].len();
+        assert_eq!(breaker.keystream().len(), len);
+        assert_eq!(breaker.column_height(0), lines.len());
+        assert_eq!(breaker.column_height(len - 1), 1);
+        assert_eq!(breaker.column_height(len), 0);
+        breaker.correct(0, 0, b"I");
+        // everything's right where there were plenty of bytes to go on
+        for (plaintext, line) in breaker.plaintexts().iter().zip(&lines) {
+            assert_eq!(plaintext.len(), line.len());
+            assert!((0..line.len()).all(|j| breaker.column_height(j) <= 10 || plaintext[j] == line[j]));
+        }
+        // and knowing the end of the longest line fixes the rest
+        breaker.correct(longest, 200, &lines[longest][200..]);
+        assert!(breaker.plaintexts().iter().zip(&lines).all(|(p, l)| p == l));
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end")]
+    fn corrections_stay_inside_the_ciphertext() {
+        let (_, ciphertexts) = lines();
+        FixedNonceBreaker::ragged(&ciphertexts).correct(2, 20, &[0; 100]);
+    }
+}
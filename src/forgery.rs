@@ -0,0 +1,163 @@
+// Helpers for forging ciphertexts without the key: CBC bitflipping (cryptopals
+// challenge 16) and ECB cut-and-paste (challenge 13).
+// Neither mode has any integrity protection. In CBC, flipping a bit in one
+// ciphertext block flips the same bit of the next plaintext block (and turns
+// the block it's in into garbage). In ECB, every block stands on its own, so
+// blocks from different ciphertexts under the same key can be spliced together.
+
+use cryptoutil;
+
+/// returns the mask which, XORed into the ciphertext, turns the plaintext
+/// <known> into <target> (which must be the same length)
+pub fn bitflip_mask(known: &[u8], target: &[u8]) -> Vec<u8> {
+    cryptoutil::xor_bytes(known, target)
+}
+
+/// given a CBC ciphertext with the IV as its first block, and plaintext <known>
+/// at <offset> into the plaintext, returns a ciphertext which decrypts to
+/// <target> at that offset instead
+/// plaintext offset n is controlled by ciphertext byte n (the IV shifts
+/// everything over by a block), so this just XORs in the mask there. The price is
+/// that the ciphertext block we flip decrypts to garbage, which garbles the
+/// plaintext block right before the target one. Earlier blocks are untouched,
+/// and a target in the first plaintext block only changes the IV, so nothing gets
+/// garbled at all. A target that straddles a block boundary garbles the first of
+/// its own blocks too, so keep it inside one block.
+pub fn cbc_bitflip(ciphertext: &[u8], offset: usize, known: &[u8], target: &[u8]) -> Vec<u8> {
+    assert!(offset + known.len() <= ciphertext.len(), "offset is past the end of the ciphertext");
+    let mask = bitflip_mask(known, target);
+    let mut forged = ciphertext.to_vec();
+    let flipped = cryptoutil::xor_bytes(&forged[offset..offset + mask.len()], &mask);
+    forged[offset..offset + mask.len()].copy_from_slice(&flipped);
+    forged
+}
+
+/// returns the input needed to make <payload> start on a block boundary when the
+/// oracle puts <prefix_len> bytes in front of it, along with the index of the
+/// block it will start at
+/// e.g. with 6 bytes of prefix and 16-byte blocks, the input is 10 bytes of
+/// filler followed by the payload, and the payload starts at block 1
+pub fn block_aligned_input(prefix_len: usize, block_size: usize, payload: &[u8]) -> (Vec<u8>, usize) {
+    let filler = (block_size - prefix_len % block_size) % block_size;
+    let mut input = vec![b'A'; filler];
+    input.extend_from_slice(payload);
+    (input, (prefix_len + filler) / block_size)
+}
+
+/// returns the <filler_len> that makes <prefix_len> + <filler_len> + <suffix_len>
+/// a whole number of blocks. Handy for pushing the end of a known string (e.g.
+/// "role=") right up against a block boundary so the next block can be replaced.
+pub fn filler_to_boundary(prefix_len: usize, suffix_len: usize, block_size: usize) -> usize {
+    (block_size - (prefix_len + suffix_len) % block_size) % block_size
+}
+
+/// returns block <index> of an ECB ciphertext
+pub fn ecb_block(ciphertext: &[u8], block_size: usize, index: usize) -> &[u8] {
+    &ciphertext[index * block_size..(index + 1) * block_size]
+}
+
+/// builds an ECB ciphertext out of blocks taken from other ciphertexts
+/// each piece is (ciphertext, first block, number of blocks)
+pub fn ecb_splice(pieces: &[(&[u8], usize, usize)], block_size: usize) -> Vec<u8> {
+    let mut forged = vec![];
+    for &(ciphertext, first, count) in pieces {
+        assert!((first + count) * block_size <= ciphertext.len(),
+                "piece runs past the end of its ciphertext");
+        forged.extend_from_slice(&ciphertext[first * block_size..(first + count) * block_size]);
+    }
+    forged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::Aes;
+    use modes;
+    use padding;
+
+    const BS: usize = 16;
+
+    fn cipher() -> Aes {
+        Aes::new(b"YELLOW SUBMARINE")
+    }
+
+    /// encrypts <plaintext> with CBC and puts the IV in front
+    fn cbc_with_iv(plaintext: &[u8]) -> Vec<u8> {
+        let iv = [0x24; BS];
+        let mut out = iv.to_vec();
+        out.extend(modes::cbc_encrypt(&cipher(), &iv, plaintext));
+        out
+    }
+
+    fn cbc_decrypt_with_iv(ciphertext: &[u8]) -> Vec<u8> {
+        modes::cbc_decrypt(&cipher(), &ciphertext[..BS], &ciphertext[BS..])
+    }
+
+    #[test]
+    fn bitflip_garbles_only_the_block_before() {
+        let plaintext = b"comment1=cookingcomment2=baconnnXXXXXXXXXXXXXXXX;comment3=pie!!!";
+        let offset = 2 * BS + 2;
+        let forged = cbc_bitflip(&cbc_with_iv(plaintext), offset, b"XXXXXXXXXXX", b";admin=true");
+        let decrypted = cbc_decrypt_with_iv(&forged);
+        // block 1 is the one whose ciphertext we flipped, so it's garbage
+        assert_eq!(decrypted[..BS], plaintext[..BS]);
+        assert!(decrypted[BS..2 * BS] != plaintext[BS..2 * BS]);
+        let mut expected = plaintext.to_vec();
+        expected[offset..offset + 11].copy_from_slice(b";admin=true");
+        assert_eq!(decrypted[2 * BS..], expected[2 * BS..]);
+    }
+
+    #[test]
+    fn bitflip_in_the_first_block_only_changes_the_iv() {
+        let plaintext = b"role=user;uid=10and then some more";
+        let plaintext = &plaintext[..2 * BS];
+        let ciphertext = cbc_with_iv(plaintext);
+        let forged = cbc_bitflip(&ciphertext, 5, b"user;", b"admin");
+        assert_eq!(forged[BS..], ciphertext[BS..]);
+        let mut expected = plaintext.to_vec();
+        expected[5..10].copy_from_slice(b"admin");
+        assert_eq!(cbc_decrypt_with_iv(&forged), expected);
+    }
+
+    #[test]
+    fn aligns_input_to_blocks() {
+        assert_eq!(block_aligned_input(0, BS, b"xyz"), (b"xyz".to_vec(), 0));
+        assert_eq!(block_aligned_input(6, BS, b"xyz"), ([&[b'A'; 10][..], b"xyz"].concat(), 1));
+        assert_eq!(block_aligned_input(16, BS, b"xyz"), (b"xyz".to_vec(), 1));
+        assert_eq!(block_aligned_input(17, BS, b"xyz"), ([&[b'A'; 15][..], b"xyz"].concat(), 2));
+
+        assert_eq!(filler_to_boundary(0, 0, BS), 0);
+        assert_eq!(filler_to_boundary(6, 5, BS), 5);
+        assert_eq!(filler_to_boundary(10, 6, BS), 0);
+        assert_eq!(filler_to_boundary(17, 0, BS), 15);
+        assert_eq!(filler_to_boundary(6, 13, 8), 5);
+    }
+
+    #[test]
+    fn cut_and_paste() {
+        // "email=" + input + "&role=user", with the input pushing "user" into a
+        // block of its own
+        let profile = |email: &[u8]| {
+            let mut p = b"email=".to_vec();
+            p.extend_from_slice(email);
+            p.extend_from_slice(b"&role=user");
+            modes::ecb_encrypt(&cipher(), &padding::pkcs7_pad(&p, BS))
+        };
+        let filler = filler_to_boundary(6, 6, BS);
+        let victim = profile(&vec![b'a'; filler]);
+        let (input, block) = block_aligned_input(6, BS, &padding::pkcs7_pad(b"admin", BS));
+        let admin = profile(&input);
+        assert_eq!(ecb_block(&admin, BS, block),
+                   &modes::ecb_encrypt(&cipher(), &padding::pkcs7_pad(b"admin", BS))[..]);
+
+        let forged = ecb_splice(&[(&victim, 0, 1), (&admin, block, 1)], BS);
+        let decrypted = modes::ecb_decrypt(&cipher(), &forged);
+        assert_eq!(padding::pkcs7_unpad(&decrypted, BS).unwrap(), b"email=aaaa&role=admin".to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end of its ciphertext")]
+    fn splice_stays_inside_the_ciphertext() {
+        ecb_splice(&[(&[0; 32], 1, 2)], BS);
+    }
+}
@@ -0,0 +1,42 @@
+// Traits for hash functions, so that constructions built on a hash (HMAC,
+// length extension attacks) can be written once for all of them.
+
+use md_padding::{self, Endian};
+
+/// a hash function that can be fed its input in pieces
+pub trait Hash: Sized {
+    /// the size of a digest in bytes
+    const DIGEST_SIZE: usize;
+    /// the size of the blocks the hash works on, in bytes
+    const BLOCK_SIZE: usize;
+
+    /// starts a new computation
+    fn new() -> Self;
+    /// hashes <data>
+    fn update(&mut self, data: &[u8]);
+    /// pads the message and returns the digest
+    fn finalize(self) -> Vec<u8>;
+
+    /// returns the digest of <data> in one go
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut h = Self::new();
+        h.update(data);
+        h.finalize()
+    }
+}
+
+/// a Merkle-Damgard hash whose state can be picked back up from a digest, which
+/// is all a length extension attack needs to know about it
+pub trait MdHash: Hash {
+    /// the byte order of the length in the hash's padding
+    const ENDIAN: Endian;
+
+    /// picks up hashing right after the message <digest> is the hash of, which
+    /// was <length> bytes long including its padding
+    fn resume(digest: &[u8], length: u64) -> Self;
+
+    /// returns the padding the hash adds after a message of <message_len> bytes
+    fn padding(message_len: u64) -> Vec<u8> {
+        md_padding::md_padding(message_len, Self::ENDIAN)
+    }
+}
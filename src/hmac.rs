@@ -0,0 +1,125 @@
+// HMAC (RFC 2104), generic over the hash function.
+// HMAC(K, m) = H((K' ^ opad) || H((K' ^ ipad) || m)), where K' is the key padded
+// out to a block (or hashed first, if it's longer than a block). The outer hash
+// is what stops length extension: extending the inner hash gets you nowhere
+// without being able to redo the outer one, which needs the key.
+
+use cryptoutil;
+use hash::Hash;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// an in-progress HMAC computation
+pub struct Hmac<H: Hash> {
+    inner: H,
+    /// K' ^ opad, saved for `finalize`
+    outer_key: Vec<u8>,
+}
+
+impl<H: Hash> Hmac<H> {
+    pub fn new(key: &[u8]) -> Hmac<H> {
+        let mut block_key = if key.len() > H::BLOCK_SIZE { H::digest(key) } else { key.to_vec() };
+        block_key.resize(H::BLOCK_SIZE, 0);
+
+        let mut inner = H::new();
+        inner.update(&cryptoutil::repeating_key_xor(&block_key, &[IPAD]));
+        Hmac { inner: inner, outer_key: cryptoutil::repeating_key_xor(&block_key, &[OPAD]) }
+    }
+
+    /// authenticates <data>
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// returns the MAC
+    pub fn finalize(self) -> Vec<u8> {
+        let mut outer = H::new();
+        outer.update(&self.outer_key);
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// returns the HMAC of <message> under <key>, e.g. hmac::<Sha1>(key, message)
+pub fn hmac<H: Hash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<H>::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+/// returns the HMAC of <message> under <key> as hex
+pub fn hmac_hex<H: Hash>(key: &[u8], message: &[u8]) -> String {
+    cryptoutil::bytes_to_hex(&hmac::<H>(key, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::Sha1;
+    use sha256::Sha256;
+
+    /// the keys and messages RFC 2202 and RFC 4231 share (test cases 1-5). The
+    /// long-key cases differ between the two.
+    fn common_cases() -> Vec<(Vec<u8>, Vec<u8>)> {
+        vec![
+            (vec![0x0b; 20], b"Hi There".to_vec()),
+            (b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec()),
+            (vec![0xaa; 20], vec![0xdd; 50]),
+            ((1..26).collect(), vec![0xcd; 50]),
+            (vec![0x0c; 20], b"Test With Truncation".to_vec()),
+        ]
+    }
+
+    // RFC 2202, section 3
+    #[test]
+    fn rfc_2202_hmac_sha1() {
+        let mut cases = common_cases();
+        cases.push((vec![0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec()));
+        cases.push((vec![0xaa; 80],
+                    b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data".to_vec()));
+        let expected = [
+            "b617318655057264e28bc0b6fb378c8ef146be00",
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            "125d7342b9ac11cd91a39af48aa17b4f63f175d3",
+            "4c9007f4026250c6bc8414f9bf50c86c2d7235da",
+            "4c1a03424b55e07fe7f27be1d58bb9324a9a5a04",
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+            "e8e99d0f45237d786d6bbaa7965c7808bbff1a91",
+        ];
+        for (&(ref key, ref message), &mac) in cases.iter().zip(expected.iter()) {
+            assert_eq!(hmac_hex::<Sha1>(key, message), mac);
+        }
+    }
+
+    // RFC 4231, section 4
+    #[test]
+    fn rfc_4231_hmac_sha256() {
+        let mut cases = common_cases();
+        cases.push((vec![0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec()));
+        cases.push((vec![0xaa; 131], b"This is a test using a larger than block-size key and a larger than \
+                                       block-size data. The key needs to be hashed before being used by the \
+                                       HMAC algorithm.".to_vec()));
+        let expected = [
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            // test case 5 only gives the first 128 bits
+            "a3b6167473100ee06e0c796c2955552b",
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+        ];
+        for (&(ref key, ref message), &mac) in cases.iter().zip(expected.iter()) {
+            assert!(hmac_hex::<Sha256>(key, message).starts_with(mac));
+        }
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let mut mac = Hmac::<Sha256>::new(b"Jefe");
+        mac.update(b"what do ya ");
+        mac.update(b"want for nothing?");
+        assert_eq!(mac.finalize(), hmac::<Sha256>(b"Jefe", b"what do ya want for nothing?"));
+    }
+}
@@ -0,0 +1,98 @@
+// Length extension attacks on secret-prefix MACs, i.e. MAC = H(secret || message)
+// for a Merkle-Damgard hash H (cryptopals challenges 29 and 30).
+// The MAC is the hash's internal state after hashing secret || message || padding.
+// Picking the hash back up from there and feeding it more data gives the MAC of
+// secret || message || padding || extension, without ever knowing the secret.
+// The catch is that the padding depends on the secret's length, so we guess it.
+
+use std::ops::Range;
+use hash::MdHash;
+
+/// the secret lengths `length_extension_attack` tries if you don't know better
+pub const SECRET_LEN_RANGE: Range<usize> = 0..65;
+
+/// a forged message and the MAC that goes with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forgery {
+    /// the secret length the forgery assumed
+    pub secret_len: usize,
+    /// message || glue padding || extension
+    pub message: Vec<u8>,
+    pub mac: Vec<u8>,
+}
+
+/// forges the MAC of <message> with <extension> tacked on (after the glue
+/// padding), given <mac> for <message> and assuming the secret was <secret_len>
+/// bytes long
+pub fn extend<H: MdHash>(mac: &[u8], message: &[u8], secret_len: usize, extension: &[u8]) -> Forgery {
+    let glue = H::padding((secret_len + message.len()) as u64);
+    let hashed = (secret_len + message.len() + glue.len()) as u64;
+    let mut h = H::resume(mac, hashed);
+    h.update(extension);
+
+    let mut forged = message.to_vec();
+    forged.extend(glue);
+    forged.extend_from_slice(extension);
+    Forgery { secret_len: secret_len, message: forged, mac: h.finalize() }
+}
+
+/// forges a MAC for <message> with <extension> tacked on, trying every secret
+/// length in <secret_lens> until <verify> (which checks a message and MAC, like
+/// the server would) accepts one
+/// returns None if none of them work
+pub fn length_extension_attack<H, F>(mac: &[u8],
+                                     message: &[u8],
+                                     extension: &[u8],
+                                     secret_lens: Range<usize>,
+                                     mut verify: F) -> Option<Forgery>
+    where H: MdHash, F: FnMut(&[u8], &[u8]) -> bool {
+    secret_lens.map(|len| extend::<H>(mac, message, len, extension))
+               .find(|forgery| verify(&forgery.message, &forgery.mac))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use md4::{self, Md4};
+    use sha1::{self, Sha1};
+
+    const MESSAGE: &'static [u8] = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    const EXTENSION: &'static [u8] = b";admin=true";
+
+    #[test]
+    fn forges_sha1_macs() {
+        for secret_len in &[0, 1, 16, 55, 56, 64] {
+            let secret = vec![b'k'; *secret_len];
+            let mac = sha1::secret_prefix_mac(&secret, MESSAGE);
+            let forgery = length_extension_attack::<Sha1, _>(&mac, MESSAGE, EXTENSION, SECRET_LEN_RANGE,
+                                                             |m, mac| sha1::secret_prefix_mac(&secret, m) == mac)
+                .unwrap();
+            assert_eq!(forgery.secret_len, *secret_len);
+            assert!(forgery.message.starts_with(MESSAGE) && forgery.message.ends_with(EXTENSION));
+            assert_eq!(forgery.mac, sha1::secret_prefix_mac(&secret, &forgery.message));
+        }
+    }
+
+    #[test]
+    fn forges_md4_macs() {
+        for secret_len in &[0, 1, 16, 55, 56, 64] {
+            let secret = vec![b'k'; *secret_len];
+            let mac = md4::secret_prefix_mac(&secret, MESSAGE);
+            let forgery = length_extension_attack::<Md4, _>(&mac, MESSAGE, EXTENSION, SECRET_LEN_RANGE,
+                                                            |m, mac| md4::secret_prefix_mac(&secret, m) == mac)
+                .unwrap();
+            assert_eq!(forgery.secret_len, *secret_len);
+            assert!(forgery.message.starts_with(MESSAGE) && forgery.message.ends_with(EXTENSION));
+            assert_eq!(forgery.mac, md4::secret_prefix_mac(&secret, &forgery.message));
+        }
+    }
+
+    #[test]
+    fn gives_up_outside_the_range() {
+        let secret = vec![b'k'; 20];
+        let mac = sha1::secret_prefix_mac(&secret, MESSAGE);
+        assert_eq!(length_extension_attack::<Sha1, _>(&mac, MESSAGE, EXTENSION, 0..20,
+                                                      |m, mac| sha1::secret_prefix_mac(&secret, m) == mac),
+                   None);
+    }
+}
@@ -0,0 +1,37 @@
+// Crate root for the shared modules, so they can be built (and their tests run)
+// in one go instead of only as pieces of whichever challenges happen to use them:
+//     rustc --test -o bin/tests src/lib.rs && bin/tests
+// (or just ./test). The challenge binaries don't use this; they pull in the
+// modules they need with #[path].
+
+pub mod aes;
+pub mod bignum;
+pub mod cbc_key_iv_attack;
+pub mod classical;
+pub mod cryptoutil;
+pub mod ctr_edit_attack;
+pub mod dh;
+pub mod dh_mitm;
+pub mod ecb_attack;
+pub mod ecb_detect;
+pub mod entropy;
+pub mod fixed_nonce;
+pub mod forgery;
+pub mod hash;
+pub mod hmac;
+pub mod length_extension;
+pub mod md4;
+pub mod md_padding;
+pub mod modes;
+pub mod mt19937;
+pub mod mt_stream;
+pub mod ngram;
+pub mod oracle;
+pub mod padding;
+pub mod padding_oracle;
+pub mod rng;
+pub mod sha1;
+pub mod sha256;
+pub mod substitution_solver;
+pub mod timing_leak;
+pub mod transposition;
@@ -0,0 +1,193 @@
+// MD4 (RFC 1320).
+// Long broken, but still a Merkle-Damgard hash like SHA-1, and resumable in the
+// same way (see `Md4::resume`). The differences that matter for length extension
+// are that it's little-endian, and its digest is only 16 bytes.
+
+use cryptoutil;
+use hash::{Hash, MdHash};
+use md_padding::{self, Endian};
+
+/// the size of a digest in bytes
+pub const DIGEST_SIZE: usize = 16;
+
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// the order each round reads the message words in
+const ROUND_2_ORDER: [usize; 16] = [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+const ROUND_3_ORDER: [usize; 16] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+/// how far each round rotates, step by step
+const SHIFTS: [[u32; 4]; 3] = [[3, 7, 11, 19], [3, 5, 9, 13], [3, 9, 11, 15]];
+
+/// an in-progress MD4 computation
+#[derive(Clone)]
+pub struct Md4 {
+    state: [u32; 4],
+    /// bytes that don't make up a whole block yet
+    buffer: Vec<u8>,
+    /// the number of bytes hashed so far
+    length: u64,
+}
+
+impl Md4 {
+    pub fn new() -> Md4 {
+        Md4 { state: INITIAL_STATE, buffer: vec![], length: 0 }
+    }
+
+    /// picks up hashing right after the message <digest> is the hash of, which
+    /// was <length> bytes long *including* its padding
+    /// (so <length> must be a whole number of blocks)
+    pub fn resume(digest: &[u8], length: u64) -> Md4 {
+        assert_eq!(digest.len(), DIGEST_SIZE);
+        assert!(length % md_padding::BLOCK_SIZE as u64 == 0, "resumed length must be a whole number of blocks");
+        let mut state = [0u32; 4];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = md_padding::read_u32(&digest[4 * i..], Endian::Little);
+        }
+        Md4 { state: state, buffer: vec![], length: length }
+    }
+
+    /// the number of bytes hashed so far
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// hashes <data>
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        let whole = self.buffer.len() / md_padding::BLOCK_SIZE * md_padding::BLOCK_SIZE;
+        let blocks: Vec<u8> = self.buffer.drain(..whole).collect();
+        for block in blocks.chunks(md_padding::BLOCK_SIZE) {
+            self.compress(block);
+        }
+    }
+
+    /// pads the message and returns the digest
+    pub fn finalize(mut self) -> Vec<u8> {
+        let padding = md_padding::md_padding(self.length, Endian::Little);
+        self.update(&padding);
+        self.state.iter().flat_map(|&w| md_padding::u32_bytes(w, Endian::Little)).collect()
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut x = [0u32; 16];
+        for (i, word) in x.iter_mut().enumerate() {
+            *word = md_padding::read_u32(&block[4 * i..], Endian::Little);
+        }
+
+        let mut h = self.state;
+        for round in 0..3 {
+            for i in 0..16 {
+                // each step updates one of a, d, c, b (in that order, over and
+                // over) from the other three
+                let t = (4 - i % 4) % 4;
+                let (b, c, d) = (h[(t + 1) % 4], h[(t + 2) % 4], h[(t + 3) % 4]);
+                let (f, k, constant) = match round {
+                    0 => ((b & c) | (!b & d), i, 0),
+                    1 => ((b & c) | (b & d) | (c & d), ROUND_2_ORDER[i], 0x5a827999),
+                    _ => (b ^ c ^ d, ROUND_3_ORDER[i], 0x6ed9eba1),
+                };
+                h[t] = h[t].wrapping_add(f).wrapping_add(x[k]).wrapping_add(constant)
+                           .rotate_left(SHIFTS[round][i % 4]);
+            }
+        }
+        for (s, v) in self.state.iter_mut().zip(&h) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+}
+
+impl Hash for Md4 {
+    const DIGEST_SIZE: usize = DIGEST_SIZE;
+    const BLOCK_SIZE: usize = md_padding::BLOCK_SIZE;
+
+    fn new() -> Md4 {
+        Md4::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Md4::update(self, data)
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Md4::finalize(self)
+    }
+}
+
+impl MdHash for Md4 {
+    const ENDIAN: Endian = Endian::Little;
+
+    fn resume(digest: &[u8], length: u64) -> Md4 {
+        Md4::resume(digest, length)
+    }
+}
+
+/// returns the MD4 digest of <data>
+pub fn md4(data: &[u8]) -> Vec<u8> {
+    let mut h = Md4::new();
+    h.update(data);
+    h.finalize()
+}
+
+/// returns the MD4 digest of <data> as hex
+pub fn md4_hex(data: &[u8]) -> String {
+    cryptoutil::bytes_to_hex(&md4(data))
+}
+
+/// from challenge 4-30
+/// a (broken) MAC: MD4(key || message)
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut h = Md4::new();
+    h.update(key);
+    h.update(message);
+    h.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the RFC 1320 test suite
+    #[test]
+    fn known_digests() {
+        let vectors: &[(&[u8], &str)] = &[
+            (b"", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+            (b"a", "bde52cb31de33e46245e05fbdbd6fb24"),
+            (b"abc", "a448017aaf21d8525fc10ae87aa6729d"),
+            (b"message digest", "d9130a8164549fe818874806e1c7014b"),
+            (b"abcdefghijklmnopqrstuvwxyz", "d79e1c308aa5bbcdeea8ed63df412da9"),
+            (b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+             "043f8582f241db351ce627e153e7f0e4"),
+            (b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+             "e33b4ddc9c38f2199c3e7b164fcc0536"),
+        ];
+        for &(message, digest) in vectors {
+            assert_eq!(md4_hex(message), digest);
+        }
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for split in 0..data.len() {
+            let mut h = Md4::new();
+            h.update(&data[..split]);
+            h.update(&data[split..]);
+            assert_eq!(h.finalize(), md4(&data));
+        }
+    }
+
+    #[test]
+    fn resumes_after_padding() {
+        let message = b"a message";
+        let digest = md4(message);
+        let glue = md_padding::md_padding(message.len() as u64, Endian::Little);
+        let mut h = Md4::resume(&digest, (message.len() + glue.len()) as u64);
+        h.update(b"and more");
+        let mut full = message.to_vec();
+        full.extend(glue);
+        full.extend_from_slice(b"and more");
+        assert_eq!(h.finalize(), md4(&full));
+    }
+}
@@ -0,0 +1,102 @@
+// Merkle-Damgard padding, as used by MD4, SHA-1 and SHA-256.
+// The message gets a 1 bit, then zeros up to 8 bytes short of a block boundary,
+// then its length in bits as a 64-bit number. The hashes only disagree on the
+// byte order of that length. Since the padding depends on nothing but the length,
+// anyone who knows how long a message was can work out its padding without
+// knowing what it said, which is what length extension attacks rely on.
+
+/// the block size of every hash that uses this padding
+pub const BLOCK_SIZE: usize = 64;
+
+/// the byte order a hash uses for its words (and the length in its padding)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// returns the padding that goes after a message of <message_len> bytes
+pub fn md_padding(message_len: u64, endian: Endian) -> Vec<u8> {
+    let mut padding = vec![0x80];
+    let used = (message_len as usize + 1) % BLOCK_SIZE;
+    let zeros = (BLOCK_SIZE + BLOCK_SIZE - 8 - used) % BLOCK_SIZE;
+    padding.extend(vec![0; zeros]);
+    padding.extend(u64_bytes(message_len.wrapping_mul(8), endian));
+    padding
+}
+
+/// returns <message> with its padding added
+pub fn md_pad(message: &[u8], endian: Endian) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.extend(md_padding(message.len() as u64, endian));
+    padded
+}
+
+/// returns the 8 bytes of <n> in the given byte order
+pub fn u64_bytes(n: u64, endian: Endian) -> Vec<u8> {
+    let bytes: Vec<u8> = (0..8).map(|i| (n >> (8 * i)) as u8).collect();
+    match endian {
+        Endian::Little => bytes,
+        Endian::Big => bytes.into_iter().rev().collect(),
+    }
+}
+
+/// reads a 32-bit word out of the first 4 bytes of <buf>
+pub fn read_u32(buf: &[u8], endian: Endian) -> u32 {
+    let bytes = &buf[..4];
+    match endian {
+        Endian::Big => bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32),
+        Endian::Little => bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32),
+    }
+}
+
+/// returns the 4 bytes of <n> in the given byte order
+pub fn u32_bytes(n: u32, endian: Endian) -> Vec<u8> {
+    let bytes: Vec<u8> = (0..4).map(|i| (n >> (8 * i)) as u8).collect();
+    match endian {
+        Endian::Little => bytes,
+        Endian::Big => bytes.into_iter().rev().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_to_whole_blocks() {
+        for len in 0..200 {
+            let padded = md_pad(&vec![b'x'; len], Endian::Big);
+            assert_eq!(padded.len() % BLOCK_SIZE, 0);
+            // there has to be room for the 0x80 and the length, and no more
+            // padding than that needs
+            assert!(padded.len() >= len + 9 && padded.len() < len + 9 + BLOCK_SIZE);
+            assert_eq!(padded[len], 0x80);
+        }
+        // 55 bytes just fit in one block with their padding; 56 don't
+        assert_eq!(md_padding(55, Endian::Big).len(), 9);
+        assert_eq!(md_padding(56, Endian::Big).len(), 72);
+        assert_eq!(md_padding(64, Endian::Big).len(), 64);
+    }
+
+    #[test]
+    fn length_in_bits() {
+        let big = md_padding(3, Endian::Big);
+        assert_eq!(big[big.len() - 8..], [0, 0, 0, 0, 0, 0, 0, 24]);
+        let little = md_padding(3, Endian::Little);
+        assert_eq!(little[little.len() - 8..], [24, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(big[..big.len() - 8], little[..little.len() - 8]);
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(u32_bytes(0x01020304, Endian::Big), [1, 2, 3, 4]);
+        assert_eq!(u32_bytes(0x01020304, Endian::Little), [4, 3, 2, 1]);
+        assert_eq!(u64_bytes(0x0102030405060708, Endian::Big), [1, 2, 3, 4, 5, 6, 7, 8]);
+        for &endian in &[Endian::Big, Endian::Little] {
+            assert_eq!(read_u32(&u32_bytes(0xdeadbeef, endian), endian), 0xdeadbeef);
+        }
+        // only looks at the first 4 bytes
+        assert_eq!(read_u32(&[1, 2, 3, 4, 5], Endian::Big), 0x01020304);
+    }
+}
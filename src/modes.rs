@@ -0,0 +1,399 @@
+// Block cipher modes of operation, generic over any block cipher.
+// ECB and CBC need the input to be a whole number of blocks (pad it first);
+// CFB, OFB and CTR turn the block cipher into a stream cipher, so they work on
+// input of any length.
+
+use aes::{self, Aes};
+use cryptoutil;
+
+/// a keyed block cipher: something that can encrypt and decrypt single blocks
+pub trait BlockCipher {
+    /// the size of a block in bytes
+    fn block_size(&self) -> usize;
+    /// encrypts exactly one block in place
+    fn encrypt_block(&self, block: &mut [u8]);
+    /// decrypts exactly one block in place
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        aes::BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        Aes::encrypt_block(self, block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        Aes::decrypt_block(self, block)
+    }
+}
+
+/// panics unless <data> is a whole number of blocks
+fn assert_block_aligned<C: BlockCipher>(cipher: &C, data: &[u8]) {
+    assert!(data.len() % cipher.block_size() == 0,
+            "input is {} bytes, which isn't a multiple of the block size ({})",
+            data.len(), cipher.block_size());
+}
+
+/// encrypts <block> out of place
+fn encrypted<C: BlockCipher>(cipher: &C, block: &[u8]) -> Vec<u8> {
+    let mut out = block.to_vec();
+    cipher.encrypt_block(&mut out);
+    out
+}
+
+/// encrypts in electronic codebook mode: every block is encrypted on its own
+pub fn ecb_encrypt<C: BlockCipher>(cipher: &C, plaintext: &[u8]) -> Vec<u8> {
+    assert_block_aligned(cipher, plaintext);
+    let mut out = plaintext.to_vec();
+    for block in out.chunks_mut(cipher.block_size()) {
+        cipher.encrypt_block(block);
+    }
+    out
+}
+
+/// undoes `ecb_encrypt`
+pub fn ecb_decrypt<C: BlockCipher>(cipher: &C, ciphertext: &[u8]) -> Vec<u8> {
+    assert_block_aligned(cipher, ciphertext);
+    let mut out = ciphertext.to_vec();
+    for block in out.chunks_mut(cipher.block_size()) {
+        cipher.decrypt_block(block);
+    }
+    out
+}
+
+/// encrypts in cipher block chaining mode: every plaintext block is XORed with
+/// the previous ciphertext block (or the IV, for the first block) before being
+/// encrypted
+pub fn cbc_encrypt<C: BlockCipher>(cipher: &C, iv: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size());
+    assert_block_aligned(cipher, plaintext);
+    let mut out = Vec::with_capacity(plaintext.len());
+    let mut prev = iv.to_vec();
+    for block in plaintext.chunks(cipher.block_size()) {
+        prev = encrypted(cipher, &cryptoutil::repeating_key_xor(block, &prev));
+        out.extend_from_slice(&prev);
+    }
+    out
+}
+
+/// undoes `cbc_encrypt`
+pub fn cbc_decrypt<C: BlockCipher>(cipher: &C, iv: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size());
+    assert_block_aligned(cipher, ciphertext);
+    let mut out = Vec::with_capacity(ciphertext.len());
+    let mut prev = iv;
+    for block in ciphertext.chunks(cipher.block_size()) {
+        let mut decrypted = block.to_vec();
+        cipher.decrypt_block(&mut decrypted);
+        out.extend(cryptoutil::repeating_key_xor(&decrypted, prev));
+        prev = block;
+    }
+    out
+}
+
+/// encrypts in CBC mode with the key doubling as the IV
+/// this saves sending an IV along with every message, and is a terrible idea:
+/// anyone who gets to see the decryption of a ciphertext they chose can recover
+/// the key (see `cbc_key_iv_attack`)
+/// the IV has to be a block long, so this only works with AES-128: panics if <key>
+/// isn't 16 bytes
+pub fn cbc_key_as_iv_encrypt(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    assert_eq!(key.len(), aes::BLOCK_SIZE, "the key doubles as the IV, so it has to be one block long");
+    cbc_encrypt(&Aes::new(key), key, plaintext)
+}
+
+/// undoes `cbc_key_as_iv_encrypt`
+pub fn cbc_key_as_iv_decrypt(key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    assert_eq!(key.len(), aes::BLOCK_SIZE, "the key doubles as the IV, so it has to be one block long");
+    cbc_decrypt(&Aes::new(key), key, ciphertext)
+}
+
+/// encrypts in (full-block) cipher feedback mode: the previous ciphertext block
+/// (or the IV) is encrypted and XORed with the plaintext
+/// the last block may be short
+pub fn cfb_encrypt<C: BlockCipher>(cipher: &C, iv: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size());
+    let mut out = Vec::with_capacity(plaintext.len());
+    let mut prev = iv.to_vec();
+    for block in plaintext.chunks(cipher.block_size()) {
+        let keystream = encrypted(cipher, &prev);
+        prev = cryptoutil::repeating_key_xor(block, &keystream);
+        out.extend_from_slice(&prev);
+    }
+    out
+}
+
+/// undoes `cfb_encrypt`
+pub fn cfb_decrypt<C: BlockCipher>(cipher: &C, iv: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size());
+    let mut out = Vec::with_capacity(ciphertext.len());
+    let mut prev = iv;
+    for block in ciphertext.chunks(cipher.block_size()) {
+        let keystream = encrypted(cipher, prev);
+        out.extend(cryptoutil::repeating_key_xor(block, &keystream));
+        prev = block;
+    }
+    out
+}
+
+/// encrypts in output feedback mode: the IV is encrypted over and over to make
+/// a keystream, which is XORed with the plaintext
+/// the last block may be short
+pub fn ofb_encrypt<C: BlockCipher>(cipher: &C, iv: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size());
+    let mut out = Vec::with_capacity(plaintext.len());
+    let mut keystream = iv.to_vec();
+    for block in plaintext.chunks(cipher.block_size()) {
+        cipher.encrypt_block(&mut keystream);
+        out.extend(cryptoutil::repeating_key_xor(block, &keystream));
+    }
+    out
+}
+
+/// undoes `ofb_encrypt` (which is the same thing as doing it again)
+pub fn ofb_decrypt<C: BlockCipher>(cipher: &C, iv: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    ofb_encrypt(cipher, iv, ciphertext)
+}
+
+/// returns the CTR keystream block for the given nonce and block counter
+/// the counter block is laid out the way cryptopals does it: the 64-bit nonce
+/// followed by the 64-bit block counter, both little-endian
+pub fn ctr_keystream_block<C: BlockCipher>(cipher: &C, nonce: u64, counter: u64) -> Vec<u8> {
+    assert_eq!(cipher.block_size(), 16, "CTR mode needs a 16-byte block");
+    let mut block = Vec::with_capacity(16);
+    for i in 0..8 {
+        block.push((nonce >> (8*i)) as u8);
+    }
+    for i in 0..8 {
+        block.push((counter >> (8*i)) as u8);
+    }
+    cipher.encrypt_block(&mut block);
+    block
+}
+
+/// encrypts in counter mode: the keystream is the encryption of successive
+/// counter blocks (see `ctr_keystream_block`), XORed with the plaintext
+/// the last block may be short
+pub fn ctr_encrypt<C: BlockCipher>(cipher: &C, nonce: u64, plaintext: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(plaintext.len());
+    for (counter, block) in plaintext.chunks(cipher.block_size()).enumerate() {
+        let keystream = ctr_keystream_block(cipher, nonce, counter as u64);
+        out.extend(cryptoutil::repeating_key_xor(block, &keystream));
+    }
+    out
+}
+
+/// undoes `ctr_encrypt` (which is the same thing as doing it again)
+pub fn ctr_decrypt<C: BlockCipher>(cipher: &C, nonce: u64, ciphertext: &[u8]) -> Vec<u8> {
+    ctr_encrypt(cipher, nonce, ciphertext)
+}
+
+/// returns <len> bytes of the CTR keystream, starting <offset> bytes into it
+/// only the blocks that overlap those bytes get computed, so seeking deep into a
+/// stream costs the same as reading from the start
+pub fn ctr_keystream<C: BlockCipher>(cipher: &C, nonce: u64, offset: usize, len: usize) -> Vec<u8> {
+    let bs = cipher.block_size();
+    let (first, last) = (offset / bs, (offset + len + bs - 1) / bs);
+    let mut keystream = Vec::with_capacity((last - first) * bs);
+    for counter in first..last {
+        keystream.extend(ctr_keystream_block(cipher, nonce, counter as u64));
+    }
+    let skip = offset % bs;
+    keystream[skip..skip + len].to_vec()
+}
+
+/// encrypts (or decrypts) <data> as if it started <offset> bytes into the stream
+/// `ctr_encrypt` is the special case where <offset> is 0
+pub fn ctr_apply_at<C: BlockCipher>(cipher: &C, nonce: u64, offset: usize, data: &[u8]) -> Vec<u8> {
+    cryptoutil::xor_bytes(data, &ctr_keystream(cipher, nonce, offset, data.len()))
+}
+
+/// returns <ciphertext> with the plaintext at <offset> replaced by <newtext>
+/// only the replaced bytes get re-encrypted. <newtext> may run past the end of
+/// the ciphertext (which then grows), but <offset> can't, since that would leave
+/// a hole.
+/// note that this re-uses the keystream: anyone who sees the ciphertext before
+/// and after an edit learns the XOR of the old and new plaintext
+pub fn ctr_edit<C: BlockCipher>(cipher: &C, nonce: u64, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Vec<u8> {
+    assert!(offset <= ciphertext.len(), "can't edit past the end of the ciphertext");
+    let mut out = ciphertext[..offset].to_vec();
+    out.extend(ctr_apply_at(cipher, nonce, offset, newtext));
+    if offset + newtext.len() < ciphertext.len() {
+        out.extend_from_slice(&ciphertext[offset + newtext.len()..]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::Aes;
+    use cryptoutil::{self, bytes_to_hex, hex_to_bytes};
+
+    // NIST SP 800-38A, appendix F: AES-128 with the same key and plaintext for
+    // every mode
+    const KEY: &'static str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const IV: &'static str = "000102030405060708090a0b0c0d0e0f";
+    const PLAINTEXT: &'static str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                                     30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn cipher() -> Aes {
+        Aes::new(&hex_to_bytes(KEY))
+    }
+
+    #[test]
+    fn ecb_vectors() {
+        let ciphertext = "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf\
+                          43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4";
+        assert_eq!(bytes_to_hex(&ecb_encrypt(&cipher(), &hex_to_bytes(PLAINTEXT))), ciphertext);
+        assert_eq!(bytes_to_hex(&ecb_decrypt(&cipher(), &hex_to_bytes(ciphertext))), PLAINTEXT);
+    }
+
+    #[test]
+    fn cbc_vectors() {
+        let ciphertext = "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2\
+                          73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7";
+        let iv = hex_to_bytes(IV);
+        assert_eq!(bytes_to_hex(&cbc_encrypt(&cipher(), &iv, &hex_to_bytes(PLAINTEXT))), ciphertext);
+        assert_eq!(bytes_to_hex(&cbc_decrypt(&cipher(), &iv, &hex_to_bytes(ciphertext))), PLAINTEXT);
+    }
+
+    #[test]
+    fn cfb_vectors() {
+        let ciphertext = "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                          26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6";
+        let iv = hex_to_bytes(IV);
+        assert_eq!(bytes_to_hex(&cfb_encrypt(&cipher(), &iv, &hex_to_bytes(PLAINTEXT))), ciphertext);
+        assert_eq!(bytes_to_hex(&cfb_decrypt(&cipher(), &iv, &hex_to_bytes(ciphertext))), PLAINTEXT);
+    }
+
+    #[test]
+    fn ofb_vectors() {
+        let ciphertext = "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+                          9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e";
+        let iv = hex_to_bytes(IV);
+        assert_eq!(bytes_to_hex(&ofb_encrypt(&cipher(), &iv, &hex_to_bytes(PLAINTEXT))), ciphertext);
+        assert_eq!(bytes_to_hex(&ofb_decrypt(&cipher(), &iv, &hex_to_bytes(ciphertext))), PLAINTEXT);
+    }
+
+    #[test]
+    fn ctr_vectors() {
+        // SP 800-38A counts up from f0f1..feff as one big-endian number, which
+        // in our nonce || counter layout (both little-endian) is this nonce
+        // followed by these counters
+        let nonce = 0xf7f6f5f4f3f2f1f0;
+        let counters = [0xfffefdfcfbfaf9f8, 0x00fffdfcfbfaf9f8, 0x01fffdfcfbfaf9f8, 0x02fffdfcfbfaf9f8];
+        let keystream: Vec<u8> = counters.iter().flat_map(|&c| ctr_keystream_block(&cipher(), nonce, c)).collect();
+        assert_eq!(bytes_to_hex(&cryptoutil::xor_bytes(&hex_to_bytes(PLAINTEXT), &keystream)),
+                   "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+                    5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee");
+
+        // and challenge 18, which uses our layout from counter 0 and ends in a
+        // short block
+        let ciphertext = hex_to_bytes(&cryptoutil::base64_to_hex(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ=="));
+        assert_eq!(ctr_decrypt(&Aes::new(b"YELLOW SUBMARINE"), 0, &ciphertext),
+                   b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ".to_vec());
+    }
+
+    #[test]
+    fn stream_modes_take_short_blocks() {
+        let (iv, plaintext) = (hex_to_bytes(IV), hex_to_bytes(PLAINTEXT));
+        for len in 0..plaintext.len() {
+            let plaintext = &plaintext[..len];
+            // a stream mode's ciphertext is as long as the plaintext, and is the
+            // start of the ciphertext of anything longer
+            let ciphertext = cfb_encrypt(&cipher(), &iv, plaintext);
+            assert_eq!(ciphertext[..], cfb_encrypt(&cipher(), &iv, &hex_to_bytes(PLAINTEXT))[..len]);
+            assert_eq!(cfb_decrypt(&cipher(), &iv, &ciphertext), plaintext);
+            let ciphertext = ofb_encrypt(&cipher(), &iv, plaintext);
+            assert_eq!(ciphertext[..], ofb_encrypt(&cipher(), &iv, &hex_to_bytes(PLAINTEXT))[..len]);
+            assert_eq!(ofb_decrypt(&cipher(), &iv, &ciphertext), plaintext);
+            let ciphertext = ctr_encrypt(&cipher(), 18, plaintext);
+            assert_eq!(ciphertext[..], ctr_encrypt(&cipher(), 18, &hex_to_bytes(PLAINTEXT))[..len]);
+            assert_eq!(ctr_decrypt(&cipher(), 18, &ciphertext), plaintext);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a multiple of the block size")]
+    fn ecb_encrypt_needs_whole_blocks() {
+        ecb_encrypt(&cipher(), &[0; 17]);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a multiple of the block size")]
+    fn ecb_decrypt_needs_whole_blocks() {
+        ecb_decrypt(&cipher(), &[0; 15]);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a multiple of the block size")]
+    fn cbc_encrypt_needs_whole_blocks() {
+        cbc_encrypt(&cipher(), &[0; 16], &[0; 31]);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't a multiple of the block size")]
+    fn cbc_decrypt_needs_whole_blocks() {
+        cbc_decrypt(&cipher(), &[0; 16], &[0; 1]);
+    }
+
+    #[test]
+    fn ctr_keystream_seeks() {
+        let full = ctr_encrypt(&cipher(), 25, &[0; 100]);
+        for offset in 0..60 {
+            for &len in &[0, 1, 15, 16, 17, 40] {
+                assert_eq!(ctr_keystream(&cipher(), 25, offset, len)[..], full[offset..offset + len]);
+            }
+        }
+        let plaintext = hex_to_bytes(PLAINTEXT);
+        let ciphertext = ctr_encrypt(&cipher(), 25, &plaintext);
+        assert_eq!(ctr_apply_at(&cipher(), 25, 21, &plaintext[21..]), ciphertext[21..].to_vec());
+    }
+
+    #[test]
+    fn ctr_edits() {
+        let plaintext = hex_to_bytes(PLAINTEXT);
+        let ciphertext = ctr_encrypt(&cipher(), 25, &plaintext);
+        // in the middle, starting partway into a block
+        let edited = ctr_edit(&cipher(), 25, &ciphertext, 21, b"new text");
+        let mut expected = plaintext.clone();
+        expected[21..29].copy_from_slice(b"new text");
+        assert_eq!(ctr_decrypt(&cipher(), 25, &edited), expected);
+        // running off the end
+        let edited = ctr_edit(&cipher(), 25, &ciphertext, 60, b"new text");
+        let mut expected = plaintext[..60].to_vec();
+        expected.extend_from_slice(b"new text");
+        assert_eq!(ctr_decrypt(&cipher(), 25, &edited), expected);
+        // and starting right at it
+        let edited = ctr_edit(&cipher(), 25, &ciphertext, 64, b"new text");
+        let mut expected = plaintext.clone();
+        expected.extend_from_slice(b"new text");
+        assert_eq!(ctr_decrypt(&cipher(), 25, &edited), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end")]
+    fn ctr_edit_leaves_no_holes() {
+        ctr_edit(&cipher(), 25, &[0; 10], 11, b"x");
+    }
+
+    #[test]
+    fn cbc_key_as_iv_round_trip() {
+        let key = b"YELLOW SUBMARINE";
+        let plaintext = b"exactly two blocks of plaintext!";
+        let ciphertext = cbc_key_as_iv_encrypt(key, plaintext);
+        assert_eq!(ciphertext, cbc_encrypt(&Aes::new(key), key, plaintext));
+        assert_eq!(cbc_key_as_iv_decrypt(key, &ciphertext), plaintext.to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "one block long")]
+    fn cbc_key_as_iv_needs_a_block_sized_key() {
+        cbc_key_as_iv_encrypt(&[0; 32], &[0; 16]);
+    }
+}
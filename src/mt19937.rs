@@ -0,0 +1,292 @@
+// The MT19937 Mersenne Twister, in its 32-bit and 64-bit flavours (cryptopals
+// challenges 21-23).
+// It's a fine generator for simulations and a terrible one for keys: every
+// output is just a scrambled ("tempered") copy of one word of the internal
+// state, and tempering is invertible. Seeing a full state's worth of consecutive
+// outputs is enough to clone the generator, and a generator seeded with the
+// current time can be found by trying every recent second.
+
+const N: usize = 624;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908b0df;
+const UPPER_MASK: u32 = 0x80000000;
+const LOWER_MASK: u32 = 0x7fffffff;
+
+const N_64: usize = 312;
+const M_64: usize = 156;
+const MATRIX_A_64: u64 = 0xb5026f5aa96619e9;
+const UPPER_MASK_64: u64 = 0xffffffff80000000;
+const LOWER_MASK_64: u64 = 0x7fffffff;
+
+/// the number of consecutive 32-bit outputs needed to clone a generator
+pub const STATE_WORDS: usize = N;
+/// the number of consecutive 64-bit outputs needed to clone a 64-bit generator
+pub const STATE_WORDS_64: usize = N_64;
+
+/// the 32-bit Mersenne Twister
+#[derive(Clone)]
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    /// seeds the generator the way the reference implementation's
+    /// init_genrand does
+    pub fn new(seed: u32) -> Mt19937 {
+        let mut state = [0u32; N];
+        state[0] = seed;
+        for i in 1..N {
+            let prev = state[i - 1];
+            state[i] = 1812433253u32.wrapping_mul(prev ^ (prev >> 30)).wrapping_add(i as u32);
+        }
+        Mt19937 { state: state, index: N }
+    }
+
+    /// a generator whose next outputs come from twisting <state>, which is how a
+    /// cloned generator picks up where the original left off
+    pub fn from_state(state: [u32; N]) -> Mt19937 {
+        Mt19937 { state: state, index: N }
+    }
+
+    /// regenerates the whole state once every output from it has been used
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mag = if y & 1 == 0 { 0 } else { MATRIX_A };
+            self.state[i] = self.state[(i + M) % N] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+
+    /// returns the next 32 random bits
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        temper(y)
+    }
+}
+
+/// the 64-bit Mersenne Twister (MT19937-64)
+#[derive(Clone)]
+pub struct Mt19937_64 {
+    state: [u64; N_64],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    /// seeds the generator the way the reference implementation's init_genrand64
+    /// does
+    pub fn new(seed: u64) -> Mt19937_64 {
+        let mut state = [0u64; N_64];
+        state[0] = seed;
+        for i in 1..N_64 {
+            let prev = state[i - 1];
+            state[i] = 6364136223846793005u64.wrapping_mul(prev ^ (prev >> 62)).wrapping_add(i as u64);
+        }
+        Mt19937_64 { state: state, index: N_64 }
+    }
+
+    /// the 64-bit counterpart of `Mt19937::from_state`
+    pub fn from_state(state: [u64; N_64]) -> Mt19937_64 {
+        Mt19937_64 { state: state, index: N_64 }
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N_64 {
+            let y = (self.state[i] & UPPER_MASK_64) | (self.state[(i + 1) % N_64] & LOWER_MASK_64);
+            let mag = if y & 1 == 0 { 0 } else { MATRIX_A_64 };
+            self.state[i] = self.state[(i + M_64) % N_64] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+
+    /// returns the next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        if self.index >= N_64 {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        temper_64(y)
+    }
+}
+
+/// undoes y ^= (y >> shift) & mask
+/// each pass gets another <shift> bits right, starting from the top ones (which
+/// the shift leaves alone), so 64 / shift passes always get all of them
+fn undo_right_shift(y: u64, shift: u32, mask: u64) -> u64 {
+    let mut x = y;
+    for _ in 0..64 / shift {
+        x = y ^ ((x >> shift) & mask);
+    }
+    x
+}
+
+/// undoes y ^= (y << shift) & mask, the same way as `undo_right_shift` but from
+/// the bottom up
+fn undo_left_shift(y: u64, shift: u32, mask: u64) -> u64 {
+    let mut x = y;
+    for _ in 0..64 / shift {
+        x = y ^ ((x << shift) & mask);
+    }
+    x
+}
+
+/// scrambles a word of 32-bit state into an output
+pub fn temper(y: u32) -> u32 {
+    let mut y = y;
+    y ^= y >> 11;
+    y ^= (y << 7) & 0x9d2c5680;
+    y ^= (y << 15) & 0xefc60000;
+    y ^ (y >> 18)
+}
+
+/// recovers the word of state an output was tempered from
+pub fn untemper(y: u32) -> u32 {
+    let mut y = y as u64;
+    y = undo_right_shift(y, 18, 0xffffffff);
+    y = undo_left_shift(y, 15, 0xefc60000);
+    y = undo_left_shift(y, 7, 0x9d2c5680);
+    undo_right_shift(y, 11, 0xffffffff) as u32
+}
+
+/// scrambles a word of 64-bit state into an output
+pub fn temper_64(y: u64) -> u64 {
+    let mut y = y;
+    y ^= (y >> 29) & 0x5555555555555555;
+    y ^= (y << 17) & 0x71d67fffeda60000;
+    y ^= (y << 37) & 0xfff7eee000000000;
+    y ^ (y >> 43)
+}
+
+/// the 64-bit counterpart of `untemper`
+pub fn untemper_64(y: u64) -> u64 {
+    let mut y = undo_right_shift(y, 43, !0);
+    y = undo_left_shift(y, 37, 0xfff7eee000000000);
+    y = undo_left_shift(y, 17, 0x71d67fffeda60000);
+    undo_right_shift(y, 29, 0x5555555555555555)
+}
+
+/// given `STATE_WORDS` consecutive outputs, starting right after a twist (e.g.
+/// the first ones out of a freshly seeded generator), returns a generator that
+/// will produce the same outputs as the original from then on
+/// returns None if there aren't enough outputs
+pub fn clone_from_outputs(outputs: &[u32]) -> Option<Mt19937> {
+    if outputs.len() < N {
+        return None;
+    }
+    let mut state = [0u32; N];
+    for (word, &output) in state.iter_mut().zip(outputs) {
+        *word = untemper(output);
+    }
+    Some(Mt19937::from_state(state))
+}
+
+/// the 64-bit counterpart of `clone_from_outputs`, which needs `STATE_WORDS_64`
+/// outputs
+pub fn clone_from_outputs_64(outputs: &[u64]) -> Option<Mt19937_64> {
+    if outputs.len() < N_64 {
+        return None;
+    }
+    let mut state = [0u64; N_64];
+    for (word, &output) in state.iter_mut().zip(outputs) {
+        *word = untemper_64(output);
+    }
+    Some(Mt19937_64::from_state(state))
+}
+
+/// tries every seed in <seeds> (a range, say), and returns the first one whose
+/// generator starts with <outputs>
+pub fn crack_seed<S: IntoIterator<Item = u32>>(outputs: &[u32], seeds: S) -> Option<u32> {
+    seeds.into_iter().find(|&seed| {
+        let mut mt = Mt19937::new(seed);
+        outputs.iter().all(|&output| mt.next_u32() == output)
+    })
+}
+
+/// the 64-bit counterpart of `crack_seed`
+pub fn crack_seed_64<S: IntoIterator<Item = u64>>(outputs: &[u64], seeds: S) -> Option<u64> {
+    seeds.into_iter().find(|&seed| {
+        let mut mt = Mt19937_64::new(seed);
+        outputs.iter().all(|&output| mt.next_u64() == output)
+    })
+}
+
+/// finds the seed of a generator that was seeded with a unix timestamp at most
+/// <window> seconds before <now>, given its first outputs
+pub fn crack_timestamp_seed(outputs: &[u32], now: u32, window: u32) -> Option<u32> {
+    crack_seed(outputs, now.saturating_sub(window)..=now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the outputs the reference implementations give for their default seed
+    #[test]
+    fn reference_outputs() {
+        let mut mt = Mt19937::new(5489);
+        assert_eq!(mt.next_u32(), 3499211612);
+        assert_eq!((1..10000).map(|_| mt.next_u32()).last(), Some(4123659995));
+        let mut mt = Mt19937_64::new(5489);
+        assert_eq!(mt.next_u64(), 14514284786278117030);
+        assert_eq!((1..10000).map(|_| mt.next_u64()).last(), Some(9981545732273789042));
+    }
+
+    #[test]
+    fn untemper_undoes_temper() {
+        let mut mt = Mt19937_64::new(41);
+        for y in vec![0, 1, !0, 0x80000000].into_iter().chain((0..1000).map(|_| mt.next_u64())) {
+            assert_eq!(untemper(temper(y as u32)), y as u32);
+            assert_eq!(untemper_64(temper_64(y)), y);
+        }
+    }
+
+    #[test]
+    fn clones_from_outputs() {
+        let mut mt = Mt19937::new(41);
+        let outputs: Vec<u32> = (0..STATE_WORDS).map(|_| mt.next_u32()).collect();
+        assert!(clone_from_outputs(&outputs[1..]).is_none());
+        let mut clone = clone_from_outputs(&outputs).unwrap();
+        for _ in 0..2000 {
+            assert_eq!(clone.next_u32(), mt.next_u32());
+        }
+
+        let mut mt = Mt19937_64::new(41);
+        let outputs: Vec<u64> = (0..STATE_WORDS_64).map(|_| mt.next_u64()).collect();
+        assert!(clone_from_outputs_64(&outputs[1..]).is_none());
+        let mut clone = clone_from_outputs_64(&outputs).unwrap();
+        for _ in 0..2000 {
+            assert_eq!(clone.next_u64(), mt.next_u64());
+        }
+    }
+
+    #[test]
+    fn cracks_seeds() {
+        let outputs: Vec<u32> = {
+            let mut mt = Mt19937::new(1234);
+            (0..2).map(|_| mt.next_u32()).collect()
+        };
+        assert_eq!(crack_seed(&outputs, 1000..2000), Some(1234));
+        assert_eq!(crack_seed(&outputs, 0..1234), None);
+        let outputs = [Mt19937_64::new(1234).next_u64()];
+        assert_eq!(crack_seed_64(&outputs, 1000..2000), Some(1234));
+    }
+
+    #[test]
+    fn cracks_timestamp_seeds() {
+        let now = 1500000000;
+        for &seed in &[now, now - 1, now - 100] {
+            assert_eq!(crack_timestamp_seed(&[Mt19937::new(seed).next_u32()], now, 100), Some(seed));
+        }
+        assert_eq!(crack_timestamp_seed(&[Mt19937::new(now - 101).next_u32()], now, 100), None);
+        // the window can't run off either end
+        let max = ::std::u32::MAX;
+        assert_eq!(crack_timestamp_seed(&[Mt19937::new(max).next_u32()], max, 10), Some(max));
+        assert_eq!(crack_timestamp_seed(&[Mt19937::new(0).next_u32()], 5, 10), Some(0));
+    }
+}
@@ -0,0 +1,106 @@
+// A stream cipher keyed by an MT19937 seed, and what's wrong with it (cryptopals
+// challenge 24).
+// It's the repeating-key XOR from challenge 5 with the key swapped out for a
+// keystream as long as the message. The trouble is that the "key" is just a seed,
+// and seeds are small: a 16-bit one falls to trying all 65536 of them, and one
+// taken from the clock falls to trying every recent second.
+
+use cryptoutil;
+use mt19937::Mt19937;
+
+/// returns the first <len> bytes of keystream from a generator seeded with <seed>
+/// each 32-bit output gives four bytes, least significant first
+pub fn mt_keystream(seed: u32, len: usize) -> Vec<u8> {
+    let mut mt = Mt19937::new(seed);
+    let mut keystream = Vec::with_capacity(len + 3);
+    while keystream.len() < len {
+        let word = mt.next_u32();
+        keystream.extend_from_slice(&[word as u8, (word >> 8) as u8, (word >> 16) as u8, (word >> 24) as u8]);
+    }
+    keystream.truncate(len);
+    keystream
+}
+
+/// encrypts by XORing with the keystream from `mt_keystream`
+pub fn mt_encrypt(seed: u32, plaintext: &[u8]) -> Vec<u8> {
+    if plaintext.is_empty() {
+        return vec![];
+    }
+    cryptoutil::repeating_key_xor(plaintext, &mt_keystream(seed, plaintext.len()))
+}
+
+/// undoes `mt_encrypt` (which is the same thing as doing it again)
+pub fn mt_decrypt(seed: u32, ciphertext: &[u8]) -> Vec<u8> {
+    mt_encrypt(seed, ciphertext)
+}
+
+/// returns true if the keystream for <seed> turns <ciphertext> into <known> at
+/// <offset>
+fn matches(seed: u32, ciphertext: &[u8], offset: usize, known: &[u8]) -> bool {
+    let keystream = mt_keystream(seed, offset + known.len());
+    cryptoutil::xor_bytes(&ciphertext[offset..offset + known.len()], &keystream[offset..]) == known
+}
+
+/// recovers the 16-bit seed <ciphertext> was encrypted with, given that the
+/// plaintext has <known> at <offset> (e.g. an attacker-controlled suffix after a
+/// random prefix, in which case offset = ciphertext.len() - known.len())
+/// a few bytes of known plaintext are enough; with fewer than 3, expect false
+/// positives
+pub fn recover_seed_16(ciphertext: &[u8], offset: usize, known: &[u8]) -> Option<u16> {
+    assert!(offset + known.len() <= ciphertext.len(), "known plaintext runs past the end of the ciphertext");
+    (0..0x10000u32).find(|&seed| matches(seed, ciphertext, offset, known)).map(|seed| seed as u16)
+}
+
+/// makes a password reset token from the first <len> bytes of keystream for
+/// <seed>, which is how you'd get one by seeding with the current time
+pub fn reset_token(seed: u32, len: usize) -> Vec<u8> {
+    mt_keystream(seed, len)
+}
+
+/// checks whether <token> came from a generator seeded with a unix timestamp at
+/// most <window> seconds before <now>, and if so returns the timestamp
+pub fn time_seeded_token(token: &[u8], now: u32, window: u32) -> Option<u32> {
+    (now.saturating_sub(window)..=now)
+        .find(|&seed| mt_keystream(seed, token.len()) == token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let plaintext = b"a message that's longer than a few words of keystream";
+        let ciphertext = mt_encrypt(0xbeef, plaintext);
+        assert!(ciphertext != plaintext.to_vec());
+        assert_eq!(mt_decrypt(0xbeef, &ciphertext), plaintext.to_vec());
+        assert_eq!(mt_encrypt(0xbeef, b""), b"");
+        // a prefix of the keystream is the keystream for a shorter message
+        assert_eq!(mt_keystream(7, 5)[..], mt_keystream(7, 100)[..5]);
+    }
+
+    #[test]
+    fn recovers_16_bit_seeds() {
+        let mut plaintext = b"random prefix!".to_vec();
+        plaintext.extend_from_slice(b"AAAAAAAAAAAAAA");
+        for &seed in &[0u16, 0x1234] {
+            let ciphertext = mt_encrypt(seed as u32, &plaintext);
+            assert_eq!(recover_seed_16(&ciphertext, 14, b"AAAAAAAAAAAAAA"), Some(seed));
+        }
+        // a seed that doesn't fit in 16 bits can't be found
+        let ciphertext = mt_encrypt(0x10000, &plaintext);
+        assert_eq!(recover_seed_16(&ciphertext, 14, b"AAAAAAAAAAAAAA"), None);
+    }
+
+    #[test]
+    fn detects_time_seeded_tokens() {
+        let now = 1500000000;
+        for &seed in &[now, now - 1, now - 600] {
+            assert_eq!(time_seeded_token(&reset_token(seed, 16), now, 600), Some(seed));
+        }
+        assert_eq!(time_seeded_token(&reset_token(now - 601, 16), now, 600), None);
+        assert_eq!(time_seeded_token(&[0x5a; 16], now, 600), None);
+        let max = ::std::u32::MAX;
+        assert_eq!(time_seeded_token(&reset_token(max, 16), max, 10), Some(max));
+    }
+}
@@ -0,0 +1,114 @@
+// N-gram language models for scoring candidate plaintexts.
+// Single-letter frequencies (what `english_chi_square` looks at) can't tell apart
+// plaintexts that use the same letters in a different order, which is exactly
+// what goes wrong when breaking substitution and transposition ciphers. Scoring
+// runs of letters fixes that.
+
+/// a bundled sample of english prose, used to train `NgramModel::english`
+pub const ENGLISH_CORPUS: &'static str = include_str!("english.txt");
+
+/// log probabilities for every sequence of <n> letters
+pub struct NgramModel {
+    n: usize,
+    /// log10 probability of each n-gram, indexed by treating the n-gram as a
+    /// base-26 number
+    log_probs: Vec<f64>,
+}
+
+impl NgramModel {
+    /// trains a model of <n>-grams on the letters in <corpus> (case-insensitive;
+    /// everything that isn't a letter is skipped)
+    /// n-grams that never show up in the corpus get a floor probability a little
+    /// below that of the rarest one that does
+    pub fn from_corpus(corpus: &str, n: usize) -> NgramModel {
+        assert!(n >= 1 && n <= 5, "n-grams must be between 1 and 5 letters long");
+        let letters = to_indices(corpus);
+        assert!(letters.len() >= n, "corpus is too short");
+
+        let size = 26usize.pow(n as u32);
+        let mut counts = vec![0u32; size];
+        for window in letters.windows(n) {
+            counts[index_of(window)] += 1;
+        }
+        let total = (letters.len() - n + 1) as f64;
+        let floor = (0.01 / total).log10();
+        let log_probs = counts.iter()
+            .map(|&count| if count == 0 { floor } else { (count as f64 / total).log10() })
+            .collect();
+        NgramModel { n: n, log_probs: log_probs }
+    }
+
+    /// a model of english <n>-grams trained on `ENGLISH_CORPUS`
+    /// trigrams are the sweet spot for a corpus this size; anything longer is
+    /// mostly floor values
+    pub fn english(n: usize) -> NgramModel {
+        NgramModel::from_corpus(ENGLISH_CORPUS, n)
+    }
+
+    /// the length of the n-grams this model scores
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// returns the total log10 probability of every n-gram in <letters>, where
+    /// each letter is given as its position in the alphabet (0-25)
+    /// higher (closer to zero) is better
+    pub fn score_indices(&self, letters: &[u8]) -> f64 {
+        letters.windows(self.n).map(|w| self.log_probs[index_of(w)]).sum()
+    }
+
+    /// scores the letters in <text> (everything else is skipped)
+    pub fn score(&self, text: &str) -> f64 {
+        self.score_indices(&to_indices(text))
+    }
+
+    /// like `score`, but divided by the number of n-grams, so texts of different
+    /// lengths can be compared
+    pub fn score_per_ngram(&self, text: &str) -> f64 {
+        let letters = to_indices(text);
+        if letters.len() < self.n {
+            return self.log_probs.iter().cloned().fold(0.0, f64::min);
+        }
+        self.score_indices(&letters) / (letters.len() - self.n + 1) as f64
+    }
+}
+
+/// given a string, returns the letters in it as positions in the alphabet
+pub fn to_indices(text: &str) -> Vec<u8> {
+    text.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_lowercase() as u8 - b'a')
+        .collect()
+}
+
+/// treats an n-gram of alphabet positions as a base-26 number
+fn index_of(ngram: &[u8]) -> usize {
+    ngram.iter().fold(0, |acc, &i| acc * 26 + i as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_ngrams() {
+        let model = NgramModel::from_corpus("abab", 2);
+        assert_eq!(model.n(), 2);
+        // "ab" shows up twice and "ba" once, out of 3 bigrams
+        assert!((model.score("ab") - (2.0f64 / 3.0).log10()).abs() < 1e-9);
+        assert!((model.score("ba") - (1.0f64 / 3.0).log10()).abs() < 1e-9);
+        assert!(model.score("aa") < model.score("ba"));
+        assert_eq!(to_indices("A-b c!"), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn prefers_english_order() {
+        let model = NgramModel::english(3);
+        let text = "the quick brown fox jumps over the lazy dog";
+        let mut scrambled: Vec<char> = text.chars().collect();
+        scrambled.reverse();
+        let scrambled: String = scrambled.into_iter().collect();
+        assert!(model.score(text) > model.score(&scrambled));
+        assert!(model.score_per_ngram(text) > model.score_per_ngram("xq"));
+    }
+}
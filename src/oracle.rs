@@ -0,0 +1,389 @@
+// Black-box oracles for attacks to run against.
+// An attack only ever gets to poke at an `Oracle`: hand it plaintext to encrypt,
+// ciphertext to decrypt, or ciphertext to check for validity. This way the attack
+// code doesn't know (or care) whether it's talking to one of the local stand-ins
+// below, which have a random key we can peek at in tests, or to a closure that
+// wraps something else entirely.
+// The stand-ins get their keys, IVs and prefixes from whatever `RandomSource`
+// they're given, so a seeded one makes an attack run reproducible.
+
+use std::error::Error;
+use std::fmt;
+use aes::{self, Aes};
+use cryptoutil;
+use modes;
+use padding::{self, PaddingError};
+use rng::RandomSource;
+
+/// the block cipher modes the stand-in oracles use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc,
+}
+
+/// something an attack can query
+/// every method has a default that panics, so an oracle only needs to implement
+/// the queries it actually answers
+pub trait Oracle {
+    /// encrypts attacker-chosen plaintext (which the oracle may surround with
+    /// bytes of its own first)
+    fn encrypt(&mut self, _plaintext: &[u8]) -> Vec<u8> {
+        panic!("this oracle doesn't encrypt");
+    }
+
+    /// decrypts attacker-chosen ciphertext, returning None if the oracle rejects
+    /// it (e.g. because the padding is invalid)
+    fn decrypt(&mut self, _ciphertext: &[u8]) -> Option<Vec<u8>> {
+        panic!("this oracle doesn't decrypt");
+    }
+
+    /// says whether attacker-chosen ciphertext is valid, without revealing
+    /// anything else. By default this is whether `decrypt` accepts it.
+    fn validate(&mut self, ciphertext: &[u8]) -> bool {
+        self.decrypt(ciphertext).is_some()
+    }
+
+    /// replaces the plaintext at <offset> in attacker-chosen ciphertext with
+    /// <newtext>, and returns the re-encrypted result
+    fn edit(&mut self, _ciphertext: &[u8], _offset: usize, _newtext: &[u8]) -> Vec<u8> {
+        panic!("this oracle doesn't edit");
+    }
+}
+
+/// an oracle made out of closures, for wrapping anything that isn't one of the
+/// stand-ins below
+/// queries without a closure panic, just like the `Oracle` defaults
+pub struct FnOracle<'a> {
+    encrypt: Option<Box<dyn FnMut(&[u8]) -> Vec<u8> + 'a>>,
+    decrypt: Option<Box<dyn FnMut(&[u8]) -> Option<Vec<u8>> + 'a>>,
+    validate: Option<Box<dyn FnMut(&[u8]) -> bool + 'a>>,
+    edit: Option<Box<dyn FnMut(&[u8], usize, &[u8]) -> Vec<u8> + 'a>>,
+}
+
+impl<'a> FnOracle<'a> {
+    /// an oracle that doesn't answer anything yet
+    pub fn new() -> FnOracle<'a> {
+        FnOracle { encrypt: None, decrypt: None, validate: None, edit: None }
+    }
+
+    pub fn with_encrypt<F>(mut self, f: F) -> FnOracle<'a>
+        where F: FnMut(&[u8]) -> Vec<u8> + 'a {
+        self.encrypt = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_decrypt<F>(mut self, f: F) -> FnOracle<'a>
+        where F: FnMut(&[u8]) -> Option<Vec<u8>> + 'a {
+        self.decrypt = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_validate<F>(mut self, f: F) -> FnOracle<'a>
+        where F: FnMut(&[u8]) -> bool + 'a {
+        self.validate = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_edit<F>(mut self, f: F) -> FnOracle<'a>
+        where F: FnMut(&[u8], usize, &[u8]) -> Vec<u8> + 'a {
+        self.edit = Some(Box::new(f));
+        self
+    }
+}
+
+impl<'a> Oracle for FnOracle<'a> {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let f = self.encrypt.as_mut().expect("this oracle doesn't encrypt");
+        f(plaintext)
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let f = self.decrypt.as_mut().expect("this oracle doesn't decrypt");
+        f(ciphertext)
+    }
+
+    fn validate(&mut self, ciphertext: &[u8]) -> bool {
+        match self.validate {
+            Some(ref mut f) => f(ciphertext),
+            None => self.decrypt(ciphertext).is_some(),
+        }
+    }
+
+    fn edit(&mut self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Vec<u8> {
+        let f = self.edit.as_mut().expect("this oracle doesn't edit");
+        f(ciphertext, offset, newtext)
+    }
+}
+
+/// from challenge 2-11
+/// encrypts under a fresh random key every time, with 5-10 random bytes added on
+/// either side of the plaintext, using ECB or CBC (with a random IV) at random
+pub struct EcbOrCbcOracle<R: RandomSource> {
+    rng: R,
+    last_mode: Option<Mode>,
+}
+
+impl<R: RandomSource> EcbOrCbcOracle<R> {
+    pub fn new(rng: R) -> EcbOrCbcOracle<R> {
+        EcbOrCbcOracle { rng: rng, last_mode: None }
+    }
+
+    /// the mode used for the most recent encryption, so we can check our guess
+    pub fn last_mode(&self) -> Option<Mode> {
+        self.last_mode
+    }
+}
+
+impl<R: RandomSource> Oracle for EcbOrCbcOracle<R> {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let cipher = Aes::new(&self.rng.gen_bytes(16));
+        let before = 5 + self.rng.gen_range(6);
+        let after = 5 + self.rng.gen_range(6);
+        let mut buf = self.rng.gen_bytes(before);
+        buf.extend_from_slice(plaintext);
+        buf.extend(self.rng.gen_bytes(after));
+        let buf = padding::pkcs7_pad(&buf, aes::BLOCK_SIZE);
+
+        if self.rng.gen_range(2) == 0 {
+            self.last_mode = Some(Mode::Ecb);
+            modes::ecb_encrypt(&cipher, &buf)
+        } else {
+            self.last_mode = Some(Mode::Cbc);
+            let iv = self.rng.gen_bytes(aes::BLOCK_SIZE);
+            modes::cbc_encrypt(&cipher, &iv, &buf)
+        }
+    }
+}
+
+/// from challenges 2-12 and 2-14
+/// encrypts prefix || plaintext || suffix with ECB under a random key that stays
+/// the same for the life of the oracle
+pub struct EcbAppendingOracle {
+    cipher: Aes,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+impl EcbAppendingOracle {
+    /// an oracle that appends <suffix> and doesn't prepend anything
+    pub fn new<R: RandomSource>(mut rng: R, suffix: &[u8]) -> EcbAppendingOracle {
+        EcbAppendingOracle::with_prefix(&mut rng, &[], suffix)
+    }
+
+    /// an oracle that also prepends a random number (up to <max_prefix>) of
+    /// random bytes, which are the same for every query
+    pub fn with_random_prefix<R: RandomSource>(mut rng: R, max_prefix: usize, suffix: &[u8]) -> EcbAppendingOracle {
+        let len = rng.gen_range(max_prefix + 1);
+        let prefix = rng.gen_bytes(len);
+        EcbAppendingOracle::with_prefix(&mut rng, &prefix, suffix)
+    }
+
+    fn with_prefix<R: RandomSource>(rng: &mut R, prefix: &[u8], suffix: &[u8]) -> EcbAppendingOracle {
+        EcbAppendingOracle {
+            cipher: Aes::new(&rng.gen_bytes(16)),
+            prefix: prefix.to_vec(),
+            suffix: suffix.to_vec(),
+        }
+    }
+
+    /// the secret bytes added in front of the plaintext
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+}
+
+impl Oracle for EcbAppendingOracle {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let mut buf = self.prefix.clone();
+        buf.extend_from_slice(plaintext);
+        buf.extend_from_slice(&self.suffix);
+        modes::ecb_encrypt(&self.cipher, &padding::pkcs7_pad(&buf, aes::BLOCK_SIZE))
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.is_empty() || ciphertext.len() % aes::BLOCK_SIZE != 0 {
+            return None;
+        }
+        let buf = modes::ecb_decrypt(&self.cipher, ciphertext);
+        padding::pkcs7_unpad(&buf, aes::BLOCK_SIZE).ok()
+    }
+}
+
+/// from challenges 2-16 and 3-17
+/// encrypts prefix || plaintext || suffix with CBC under a random key that stays
+/// the same for the life of the oracle, and a fresh random IV every time. The IV
+/// is sent as the first block of the ciphertext.
+/// decryption fails if the padding is invalid, which makes this a padding oracle.
+pub struct CbcOracle<R: RandomSource> {
+    cipher: Aes,
+    rng: R,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+impl<R: RandomSource> CbcOracle<R> {
+    /// an oracle that encrypts exactly what it's given
+    pub fn new(rng: R) -> CbcOracle<R> {
+        CbcOracle::with_affixes(rng, &[], &[])
+    }
+
+    /// an oracle that surrounds the plaintext with <prefix> and <suffix>
+    pub fn with_affixes(mut rng: R, prefix: &[u8], suffix: &[u8]) -> CbcOracle<R> {
+        CbcOracle {
+            cipher: Aes::new(&rng.gen_bytes(16)),
+            rng: rng,
+            prefix: prefix.to_vec(),
+            suffix: suffix.to_vec(),
+        }
+    }
+
+    /// encrypts <plaintext> exactly as given, without the prefix and suffix
+    /// (handy for making a target ciphertext to attack)
+    pub fn encrypt_raw(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let mut out = self.rng.gen_bytes(aes::BLOCK_SIZE);
+        let padded = padding::pkcs7_pad(plaintext, aes::BLOCK_SIZE);
+        let ciphertext = modes::cbc_encrypt(&self.cipher, &out, &padded);
+        out.extend(ciphertext);
+        out
+    }
+}
+
+impl<R: RandomSource> Oracle for CbcOracle<R> {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let mut buf = self.prefix.clone();
+        buf.extend_from_slice(plaintext);
+        buf.extend_from_slice(&self.suffix);
+        self.encrypt_raw(&buf)
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.len() < 2 * aes::BLOCK_SIZE || ciphertext.len() % aes::BLOCK_SIZE != 0 {
+            return None;
+        }
+        let (iv, ciphertext) = ciphertext.split_at(aes::BLOCK_SIZE);
+        let buf = modes::cbc_decrypt(&self.cipher, iv, ciphertext);
+        padding::pkcs7_unpad(&buf, aes::BLOCK_SIZE).ok()
+    }
+}
+
+/// from challenge 4-25
+/// encrypts with CTR under a random key and nonce that stay the same for the
+/// life of the oracle, and lets anyone edit ciphertexts in place (think of a disk
+/// encryption layer that supports random access writes)
+pub struct CtrEditOracle {
+    cipher: Aes,
+    nonce: u64,
+}
+
+impl CtrEditOracle {
+    pub fn new<R: RandomSource>(mut rng: R) -> CtrEditOracle {
+        CtrEditOracle { cipher: Aes::new(&rng.gen_bytes(16)), nonce: rng.next_u64() }
+    }
+}
+
+impl Oracle for CtrEditOracle {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        modes::ctr_encrypt(&self.cipher, self.nonce, plaintext)
+    }
+
+    fn edit(&mut self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Vec<u8> {
+        modes::ctr_edit(&self.cipher, self.nonce, ciphertext, offset, newtext)
+    }
+}
+
+/// why `CbcKeyAsIvOracle::receive` rejected a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveError {
+    /// the ciphertext isn't a whole number of blocks, so it can't be decrypted
+    /// at all
+    BadLength(usize),
+    /// the plaintext wasn't padded properly
+    BadPadding(PaddingError),
+    /// the plaintext has bytes that aren't ascii. The receiver helpfully includes
+    /// the offending plaintext in the error.
+    NotAscii(Vec<u8>),
+}
+
+impl fmt::Display for ReceiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReceiveError::BadLength(len) =>
+                write!(f, "ciphertext is {} bytes, which isn't a whole number of blocks", len),
+            ReceiveError::BadPadding(ref e) =>
+                write!(f, "bad padding: {}", e),
+            ReceiveError::NotAscii(ref plaintext) =>
+                write!(f, "message isn't ascii: {}", cryptoutil::bytes_to_hex(plaintext)),
+        }
+    }
+}
+
+impl Error for ReceiveError {}
+
+/// from challenge 4-27
+/// encrypts with CBC under a random key that also serves as the IV (so no IV is
+/// sent), and receives messages by checking that they decrypt to ascii
+/// as an `Oracle`, this only answers `decrypt` when the receiver leaks the
+/// plaintext in a `ReceiveError::NotAscii`; messages it accepts (or rejects for bad
+/// padding) give back None.
+pub struct CbcKeyAsIvOracle {
+    key: Vec<u8>,
+}
+
+impl CbcKeyAsIvOracle {
+    pub fn new<R: RandomSource>(mut rng: R) -> CbcKeyAsIvOracle {
+        CbcKeyAsIvOracle { key: rng.gen_bytes(aes::BLOCK_SIZE) }
+    }
+
+    /// the secret key (and IV), so we can check our answer
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// decrypts and checks a message, the way the receiving end would
+    pub fn receive(&self, ciphertext: &[u8]) -> Result<(), ReceiveError> {
+        if ciphertext.len() % aes::BLOCK_SIZE != 0 {
+            return Err(ReceiveError::BadLength(ciphertext.len()));
+        }
+        let padded = modes::cbc_key_as_iv_decrypt(&self.key, ciphertext);
+        let plaintext = padding::pkcs7_unpad(&padded, aes::BLOCK_SIZE).map_err(ReceiveError::BadPadding)?;
+        if cryptoutil::is_ascii(&plaintext) {
+            Ok(())
+        } else {
+            Err(ReceiveError::NotAscii(plaintext))
+        }
+    }
+}
+
+impl Oracle for CbcKeyAsIvOracle {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        modes::cbc_key_as_iv_encrypt(&self.key, &padding::pkcs7_pad(plaintext, aes::BLOCK_SIZE))
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.is_empty() || ciphertext.len() % aes::BLOCK_SIZE != 0 {
+            return None;
+        }
+        match self.receive(ciphertext) {
+            Err(ReceiveError::NotAscii(plaintext)) => Some(plaintext),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rng::XorShift;
+
+    #[test]
+    fn key_as_iv_receiver() {
+        let mut oracle = CbcKeyAsIvOracle::new(XorShift::new(40));
+        let ciphertext = oracle.encrypt(b"an ordinary ascii message");
+        assert_eq!(oracle.receive(&ciphertext), Ok(()));
+        assert_eq!(oracle.receive(&ciphertext[..ciphertext.len() - 1]),
+                   Err(ReceiveError::BadLength(ciphertext.len() - 1)));
+        assert_eq!(oracle.receive(&[]), Err(ReceiveError::BadPadding(PaddingError::Empty)));
+        assert_eq!(oracle.decrypt(&ciphertext[..5]), None);
+    }
+}
@@ -0,0 +1,216 @@
+// Block cipher padding schemes: PKCS#7, ANSI X.923 and ISO/IEC 7816-4.
+// Unpadding is strict and reports exactly what was wrong with the padding, since
+// the difference between "valid" and "invalid" padding is the whole basis of the
+// padding oracle attack.
+
+use std::error::Error;
+use std::fmt;
+
+/// everything that can be wrong with a padded buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingError {
+    /// there's nothing to unpad; a padded buffer is always at least one block
+    Empty,
+    /// the buffer isn't a whole number of blocks
+    NotBlockAligned { len: usize, block_size: usize },
+    /// the final byte says there are zero bytes of padding, which is never valid
+    /// (a block-aligned message gets a whole block of padding instead)
+    ZeroLength,
+    /// the final byte says there's more padding than fits in a block
+    LengthTooLarge { pad_len: usize, block_size: usize },
+    /// a byte inside the padding has the wrong value
+    BadPadByte { position: usize, expected: u8, found: u8 },
+    /// ISO/IEC 7816-4 only: there's no 0x80 marker in the final block
+    MissingMarker,
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PaddingError::Empty =>
+                write!(f, "padded buffer is empty"),
+            PaddingError::NotBlockAligned { len, block_size } =>
+                write!(f, "padded buffer is {} bytes, which isn't a multiple of the block size ({})",
+                       len, block_size),
+            PaddingError::ZeroLength =>
+                write!(f, "padding length is zero"),
+            PaddingError::LengthTooLarge { pad_len, block_size } =>
+                write!(f, "padding length {} is larger than the block size ({})", pad_len, block_size),
+            PaddingError::BadPadByte { position, expected, found } =>
+                write!(f, "expected padding byte 0x{:02x} at offset {}, found 0x{:02x}",
+                       expected, position, found),
+            PaddingError::MissingMarker =>
+                write!(f, "no 0x80 padding marker in the final block"),
+        }
+    }
+}
+
+impl Error for PaddingError {}
+
+/// panics on block sizes that can't be padded to (the padding length has to fit
+/// in a byte). Unlike anything wrong with the buffer, a bad block size is a bug
+/// in the caller, so it isn't a `PaddingError`.
+fn check_block_size(block_size: usize) {
+    assert!(block_size > 0 && block_size < 256, "block size must be between 1 and 255");
+}
+
+/// checks the length of a padded buffer
+fn check_aligned(buf: &[u8], block_size: usize) -> Result<(), PaddingError> {
+    check_block_size(block_size);
+    if buf.is_empty() {
+        Err(PaddingError::Empty)
+    } else if buf.len() % block_size != 0 {
+        Err(PaddingError::NotBlockAligned { len: buf.len(), block_size: block_size })
+    } else {
+        Ok(())
+    }
+}
+
+/// reads the padding length out of the final byte (PKCS#7 and X.923)
+fn pad_length(buf: &[u8], block_size: usize) -> Result<usize, PaddingError> {
+    check_aligned(buf, block_size)?;
+    let pad_len = buf[buf.len() - 1] as usize;
+    if pad_len == 0 {
+        Err(PaddingError::ZeroLength)
+    } else if pad_len > block_size {
+        Err(PaddingError::LengthTooLarge { pad_len: pad_len, block_size: block_size })
+    } else {
+        Ok(pad_len)
+    }
+}
+
+/// checks that every byte in buf[start..end] is <expected>
+fn check_bytes(buf: &[u8], start: usize, end: usize, expected: u8) -> Result<(), PaddingError> {
+    match (start..end).find(|&i| buf[i] != expected) {
+        Some(i) => Err(PaddingError::BadPadByte { position: i, expected: expected, found: buf[i] }),
+        None => Ok(()),
+    }
+}
+
+/// the number of bytes of padding needed to bring <len> up to a multiple of
+/// <block_size>. This is always between 1 and block_size.
+fn bytes_needed(len: usize, block_size: usize) -> usize {
+    check_block_size(block_size);
+    block_size - len % block_size
+}
+
+/// from challenge 2-9
+/// pads <buf> to a multiple of <block_size> with PKCS#7: n bytes of padding are
+/// all the value n
+pub fn pkcs7_pad(buf: &[u8], block_size: usize) -> Vec<u8> {
+    let n = bytes_needed(buf.len(), block_size);
+    let mut padded = buf.to_vec();
+    padded.extend(vec![n as u8; n]);
+    padded
+}
+
+/// from challenge 2-15
+/// removes PKCS#7 padding, or says exactly why it's invalid
+pub fn pkcs7_unpad(buf: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let n = pad_length(buf, block_size)?;
+    check_bytes(buf, buf.len() - n, buf.len(), n as u8)?;
+    Ok(buf[..buf.len() - n].to_vec())
+}
+
+/// pads <buf> with ANSI X.923: n bytes of padding are n-1 zeroes followed by the
+/// value n
+pub fn x923_pad(buf: &[u8], block_size: usize) -> Vec<u8> {
+    let n = bytes_needed(buf.len(), block_size);
+    let mut padded = buf.to_vec();
+    padded.extend(vec![0; n - 1]);
+    padded.push(n as u8);
+    padded
+}
+
+/// removes ANSI X.923 padding, or says exactly why it's invalid
+pub fn x923_unpad(buf: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let n = pad_length(buf, block_size)?;
+    check_bytes(buf, buf.len() - n, buf.len() - 1, 0)?;
+    Ok(buf[..buf.len() - n].to_vec())
+}
+
+/// pads <buf> with ISO/IEC 7816-4: a single 0x80 byte followed by as many zeroes
+/// as it takes
+pub fn iso7816_pad(buf: &[u8], block_size: usize) -> Vec<u8> {
+    let n = bytes_needed(buf.len(), block_size);
+    let mut padded = buf.to_vec();
+    padded.push(0x80);
+    padded.extend(vec![0; n - 1]);
+    padded
+}
+
+/// removes ISO/IEC 7816-4 padding, or says exactly why it's invalid
+pub fn iso7816_unpad(buf: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    check_aligned(buf, block_size)?;
+    let last_block = buf.len() - block_size;
+    // skip back over the zeroes; the first non-zero byte has to be the marker
+    match (last_block..buf.len()).rev().find(|&i| buf[i] != 0) {
+        Some(i) if buf[i] == 0x80 => Ok(buf[..i].to_vec()),
+        Some(i) => Err(PaddingError::BadPadByte { position: i, expected: 0x80, found: buf[i] }),
+        None => Err(PaddingError::MissingMarker),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for len in 0..40 {
+            let buf: Vec<u8> = (0..len as u8).collect();
+            assert_eq!(pkcs7_unpad(&pkcs7_pad(&buf, 16), 16), Ok(buf.clone()));
+            assert_eq!(x923_unpad(&x923_pad(&buf, 16), 16), Ok(buf.clone()));
+            assert_eq!(iso7816_unpad(&iso7816_pad(&buf, 16), 16), Ok(buf.clone()));
+        }
+    }
+
+    #[test]
+    fn pads() {
+        assert_eq!(pkcs7_pad(b"YELLOW SUBMARINE", 20), b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec());
+        assert_eq!(pkcs7_pad(b"", 4), vec![4; 4]);
+        assert_eq!(x923_pad(b"ab", 4), b"ab\x00\x02".to_vec());
+        assert_eq!(iso7816_pad(b"ab", 4), b"ab\x80\x00".to_vec());
+    }
+
+    #[test]
+    fn pkcs7_errors() {
+        assert_eq!(pkcs7_unpad(b"", 4), Err(PaddingError::Empty));
+        assert_eq!(pkcs7_unpad(b"abc", 4), Err(PaddingError::NotBlockAligned { len: 3, block_size: 4 }));
+        assert_eq!(pkcs7_unpad(b"abc\x00", 4), Err(PaddingError::ZeroLength));
+        assert_eq!(pkcs7_unpad(b"abc\x05", 4), Err(PaddingError::LengthTooLarge { pad_len: 5, block_size: 4 }));
+        // challenge 2-15's examples
+        assert_eq!(pkcs7_unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16),
+                   Err(PaddingError::BadPadByte { position: 11, expected: 5, found: b'Y' }));
+        assert_eq!(pkcs7_unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16),
+                   Err(PaddingError::BadPadByte { position: 12, expected: 4, found: 1 }));
+        assert_eq!(pkcs7_unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16), Ok(b"ICE ICE BABY".to_vec()));
+    }
+
+    #[test]
+    fn x923_errors() {
+        assert_eq!(x923_unpad(b"ab\x00\x00", 4), Err(PaddingError::ZeroLength));
+        assert_eq!(x923_unpad(b"ab\x01\x02", 4),
+                   Err(PaddingError::BadPadByte { position: 2, expected: 0, found: 1 }));
+    }
+
+    #[test]
+    fn iso7816_errors() {
+        assert_eq!(iso7816_unpad(b"abc", 4), Err(PaddingError::NotBlockAligned { len: 3, block_size: 4 }));
+        assert_eq!(iso7816_unpad(b"\x80\x00\x00\x00\x00\x00\x00\x00", 4), Err(PaddingError::MissingMarker));
+        assert_eq!(iso7816_unpad(b"ab\x01\x00", 4),
+                   Err(PaddingError::BadPadByte { position: 2, expected: 0x80, found: 1 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "block size")]
+    fn unpad_rejects_zero_block_size() {
+        let _ = pkcs7_unpad(b"abc\x01", 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "block size")]
+    fn pad_rejects_zero_block_size() {
+        pkcs7_pad(b"abc", 0);
+    }
+}
@@ -0,0 +1,220 @@
+// The CBC padding oracle attack (cryptopals challenge 17).
+// In CBC, plaintext block i is D(C_i) ^ C_{i-1}, and we control C_{i-1}. If the
+// receiver tells us whether the padding of what it decrypted is valid, we can
+// tweak the last byte of C_{i-1} until the padding comes out as a valid \x01,
+// which tells us the last byte of D(C_i). Then we aim for \x02\x02, and so on
+// back through the block. Once we know D(C_i), we can decrypt it (XOR with the
+// real previous block) or make it decrypt to anything we like (XOR with the
+// plaintext we want), which is how `forge` encrypts without the key.
+
+use cryptoutil;
+use oracle::Oracle;
+use padding::{self, PaddingError};
+
+/// the output of an attack: the recovered plaintext, plus how many times we
+/// had to ask the oracle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackResult {
+    pub plaintext: Vec<u8>,
+    pub queries: usize,
+}
+
+impl AttackResult {
+    /// the plaintext as a hex string
+    pub fn to_hex(&self) -> String {
+        cryptoutil::bytes_to_hex(&self.plaintext)
+    }
+
+    /// the plaintext as a base64 string
+    pub fn to_base64(&self) -> String {
+        cryptoutil::hex_to_base64(&self.to_hex())
+    }
+
+    /// the plaintext as ascii (see `cryptoutil::hex_to_ascii`)
+    pub fn to_ascii(&self) -> String {
+        cryptoutil::hex_to_ascii(&self.to_hex())
+    }
+}
+
+/// a padding oracle attack against <oracle>, whose `validate` takes an IV
+/// followed by CBC ciphertext and says whether the padding is valid
+pub struct PaddingOracleAttack<'a, O: Oracle + 'a> {
+    oracle: &'a mut O,
+    block_size: usize,
+    queries: usize,
+}
+
+impl<'a, O: Oracle> PaddingOracleAttack<'a, O> {
+    pub fn new(oracle: &'a mut O, block_size: usize) -> PaddingOracleAttack<'a, O> {
+        assert!(block_size >= 2 && block_size < 256);
+        PaddingOracleAttack { oracle: oracle, block_size: block_size, queries: 0 }
+    }
+
+    /// how many times the oracle has been asked so far
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    /// asks the oracle whether <block> has valid padding when decrypted with
+    /// <prev> as the previous block (or IV)
+    fn query(&mut self, prev: &[u8], block: &[u8]) -> bool {
+        self.queries += 1;
+        let mut ciphertext = prev.to_vec();
+        ciphertext.extend_from_slice(block);
+        self.oracle.validate(&ciphertext)
+    }
+
+    /// recovers D(block), the raw block cipher decryption of <block>, before it
+    /// gets XORed with the previous block
+    pub fn intermediate(&mut self, block: &[u8]) -> Vec<u8> {
+        let bs = self.block_size;
+        assert_eq!(block.len(), bs);
+        let mut intermediate = vec![0u8; bs];
+
+        for pos in (0..bs).rev() {
+            let pad = (bs - pos) as u8;
+            // set up the bytes we already know so they decrypt to <pad>
+            let mut prev = vec![0u8; bs];
+            for j in pos+1..bs {
+                prev[j] = intermediate[j] ^ pad;
+            }
+
+            let mut found = None;
+            for guess in 0..256 {
+                prev[pos] = guess as u8;
+                if !self.query(&prev, block) {
+                    continue;
+                }
+                if pos == bs - 1 {
+                    // For the last byte, valid padding usually means we hit \x01,
+                    // but the plaintext might happen to end in \x02\x02 (or
+                    // \x03\x03\x03...) already. Changing the second-to-last byte
+                    // breaks those, but leaves a lone \x01 valid.
+                    prev[pos - 1] ^= 0xff;
+                    let still_valid = self.query(&prev, block);
+                    prev[pos - 1] ^= 0xff;
+                    if !still_valid {
+                        continue;
+                    }
+                }
+                found = Some(guess as u8);
+                break;
+            }
+            let guess = found.expect("no guess gave valid padding; is this really a padding oracle?");
+            intermediate[pos] = guess ^ pad;
+        }
+        intermediate
+    }
+
+    /// decrypts <ciphertext> (IV first), and returns the plaintext with the
+    /// padding still on
+    pub fn decrypt_padded(&mut self, ciphertext: &[u8]) -> Vec<u8> {
+        let bs = self.block_size;
+        assert!(ciphertext.len() >= 2 * bs && ciphertext.len() % bs == 0,
+                "ciphertext must be an IV plus at least one whole block");
+        let blocks: Vec<&[u8]> = ciphertext.chunks(bs).collect();
+        let mut plaintext = Vec::with_capacity(ciphertext.len() - bs);
+        for pair in blocks.windows(2) {
+            let intermediate = self.intermediate(pair[1]);
+            plaintext.extend(cryptoutil::xor_bytes(&intermediate, pair[0]));
+        }
+        plaintext
+    }
+
+    /// decrypts <ciphertext> (IV first) and strips the padding
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<AttackResult, PaddingError> {
+        let padded = self.decrypt_padded(ciphertext);
+        let plaintext = padding::pkcs7_unpad(&padded, self.block_size)?;
+        Ok(AttackResult { plaintext: plaintext, queries: self.queries })
+    }
+
+    /// makes a ciphertext (IV first) that the oracle will decrypt to <plaintext>,
+    /// without knowing the key
+    /// we start from an arbitrary final block and work backwards, choosing each
+    /// previous block so the one after it decrypts to what we want
+    pub fn forge(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let bs = self.block_size;
+        let padded = padding::pkcs7_pad(plaintext, bs);
+        let mut blocks: Vec<Vec<u8>> = vec![vec![0; bs]];
+        for target in padded.chunks(bs).rev() {
+            let intermediate = self.intermediate(&blocks[0]);
+            blocks.insert(0, cryptoutil::xor_bytes(&intermediate, target));
+        }
+        blocks.concat()
+    }
+}
+
+/// decrypts <ciphertext> (IV first) with a padding oracle in one go
+pub fn padding_oracle_decrypt<O: Oracle>(oracle: &mut O,
+                                         block_size: usize,
+                                         ciphertext: &[u8]) -> Result<AttackResult, PaddingError> {
+    PaddingOracleAttack::new(oracle, block_size).decrypt(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use aes::{self, Aes};
+    use modes;
+    use oracle::{CbcOracle, FnOracle};
+    use rng::XorShift;
+
+    #[test]
+    fn decrypts() {
+        let mut oracle = CbcOracle::new(XorShift::new(36));
+        let plaintexts: &[&[u8]] = &[b"", b"short", b"YELLOW SUBMARINE", b"ends in a two\x02",
+                                     b"exactly a block\x02", b"ends in two twos, a block later\x02\x02"];
+        for &plaintext in plaintexts {
+            let ciphertext = oracle.encrypt(plaintext);
+            let result = padding_oracle_decrypt(&mut oracle, aes::BLOCK_SIZE, &ciphertext).unwrap();
+            assert_eq!(result.plaintext, plaintext);
+            // at most 256 guesses for each byte, plus one check for each block's
+            // last byte
+            let blocks = ciphertext.len() / aes::BLOCK_SIZE - 1;
+            assert!(result.queries > 0 && result.queries <= blocks * (256 * aes::BLOCK_SIZE + 1));
+        }
+    }
+
+    #[test]
+    fn not_fooled_by_longer_padding() {
+        // find a block that decrypts to ..\x02? when XORed with the all-zero block
+        // the attack starts from, so that ending in \x02 gives valid padding too,
+        // and where the guess that gives \x02 gets tried before the one that gives
+        // \x01
+        let cipher = Aes::new(b"YELLOW SUBMARINE");
+        let block = (0..0x10000).map(|i| {
+            let mut block = vec![0; aes::BLOCK_SIZE];
+            block[0] = i as u8;
+            block[1] = (i >> 8) as u8;
+            block
+        }).find(|block| {
+            let mut d = block.clone();
+            cipher.decrypt_block(&mut d);
+            let last = d[aes::BLOCK_SIZE - 1];
+            d[aes::BLOCK_SIZE - 2] == 2 && last ^ 2 < last ^ 1
+        }).unwrap();
+        let mut expected = block.clone();
+        cipher.decrypt_block(&mut expected);
+
+        let queries = Cell::new(0);
+        let mut oracle = FnOracle::new().with_validate(|ciphertext| {
+            queries.set(queries.get() + 1);
+            let (iv, ciphertext) = ciphertext.split_at(aes::BLOCK_SIZE);
+            padding::pkcs7_unpad(&modes::cbc_decrypt(&cipher, iv, ciphertext), aes::BLOCK_SIZE).is_ok()
+        });
+        let mut attack = PaddingOracleAttack::new(&mut oracle, aes::BLOCK_SIZE);
+        assert_eq!(attack.intermediate(&block), expected);
+        assert_eq!(attack.queries(), queries.get());
+    }
+
+    #[test]
+    fn forges() {
+        let mut oracle = CbcOracle::new(XorShift::new(36));
+        for &plaintext in &[&b""[..], b"forged without ever seeing the key", b"YELLOW SUBMARINE"] {
+            let forged = PaddingOracleAttack::new(&mut oracle, aes::BLOCK_SIZE).forge(plaintext);
+            assert_eq!(forged.len(), (plaintext.len() / aes::BLOCK_SIZE + 2) * aes::BLOCK_SIZE);
+            assert_eq!(oracle.decrypt(&forged), Some(plaintext.to_vec()));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Sources of randomness for keys, IVs, random prefixes and randomized searches.
+// Everything that needs random numbers takes a `RandomSource`, so the same code
+// can run on real randomness from the OS or on a seeded generator. The seeded one
+// makes every oracle key and every hill-climbing run come out the same each time
+// it's run with the same seed, which is what you want when checking that an
+// attack works.
+
+use std::fs::File;
+use std::io::{self, Read};
+
+/// something that produces random bits
+/// implementations only need `next_u64`; everything else is built on top of it
+pub trait RandomSource {
+    /// returns the next 64 random bits
+    fn next_u64(&mut self) -> u64;
+
+    /// returns a random number in [0, n)
+    fn gen_range(&mut self, n: usize) -> usize {
+        assert!(n > 0);
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// returns a random float in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// returns <n> random bytes
+    fn gen_bytes(&mut self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// shuffles <v> in place (fisher-yates)
+    fn shuffle<T>(&mut self, v: &mut [T]) where Self: Sized {
+        for i in (1..v.len()).rev() {
+            let j = self.gen_range(i + 1);
+            v.swap(i, j);
+        }
+    }
+}
+
+/// a tiny seedable PRNG (xorshift64*)
+/// this is NOT cryptographically secure; it's the deterministic source, for when
+/// results need to be reproducible
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// creates a generator from a seed. Any seed works, including 0.
+    pub fn new(seed: u64) -> XorShift {
+        // xorshift gets stuck at 0 forever, so scramble the seed (splitmix64)
+        // to make sure we never start there
+        let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        XorShift { state: if z == 0 { 1 } else { z } }
+    }
+}
+
+impl RandomSource for XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+}
+
+/// randomness straight from the OS (/dev/urandom)
+/// panics if reading from it ever fails after it's been opened, since there's
+/// nothing sensible to fall back on
+pub struct OsRandom {
+    urandom: File,
+}
+
+impl OsRandom {
+    pub fn new() -> io::Result<OsRandom> {
+        Ok(OsRandom { urandom: File::open("/dev/urandom")? })
+    }
+}
+
+impl RandomSource for OsRandom {
+    fn next_u64(&mut self) -> u64 {
+        let bytes = self.gen_bytes(8);
+        bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
+    }
+
+    fn gen_bytes(&mut self, n: usize) -> Vec<u8> {
+        let mut buf = vec![0; n];
+        self.urandom.read_exact(&mut buf).expect("failed to read from /dev/urandom");
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_is_repeatable() {
+        let (mut a, mut b) = (XorShift::new(43), XorShift::new(43));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_eq!(a.gen_bytes(33), b.gen_bytes(33));
+        assert!(XorShift::new(43).next_u64() != XorShift::new(44).next_u64());
+        // 0 is a seed like any other
+        let mut zero = XorShift::new(0);
+        assert!((0..100).any(|_| zero.next_u64() != 0));
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = XorShift::new(43);
+        assert!((0..100).all(|_| rng.gen_range(1) == 0));
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[rng.gen_range(7)] = true;
+            let x = rng.next_f64();
+            assert!(x >= 0.0 && x < 1.0);
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_range() {
+        XorShift::new(43).gen_range(0);
+    }
+
+    #[test]
+    fn shuffle_permutes() {
+        let mut rng = XorShift::new(43);
+        let mut v: Vec<usize> = (0..50).collect();
+        rng.shuffle(&mut v);
+        assert!(v != (0..50).collect::<Vec<_>>());
+        v.sort();
+        assert_eq!(v, (0..50).collect::<Vec<_>>());
+        let mut empty: [u8; 0] = [];
+        rng.shuffle(&mut empty);
+    }
+
+    #[test]
+    fn os_random() {
+        let mut rng = OsRandom::new().unwrap();
+        for &n in &[0, 1, 16, 1000] {
+            assert_eq!(rng.gen_bytes(n).len(), n);
+        }
+        assert!(rng.next_u64() != rng.next_u64());
+    }
+}
@@ -1,5 +1,9 @@
 #[path="../cryptoutil.rs"]
 mod cryptoutil;
+#[path="../mt19937.rs"]
+mod mt19937;
+#[path="../mt_stream.rs"]
+mod mt_stream;
 
 use std::env;
 use std::process;
@@ -10,13 +14,29 @@ use std::string::String;
  *    would be a multiple of key length; not so!)
  */
 fn main() {
-    let USAGE = "usage: challenge5_repeatingKeyXOR <key> <plaintext>";
+    let USAGE = "usage: challenge5_repeatingKeyXOR <key> <plaintext>\n       \
+                 challenge5_repeatingKeyXOR --mt <seed> <plaintext>";
     let args = env::args().collect::<Vec<_>>();
-    if args.len() < 3 {
+    if args.len() < 3 || (args[1] == "--mt" && args.len() < 4) {
         println!("{}", USAGE);
         process::exit(1);
     }
 
+    // challenge 24's stream cipher is this same XOR, with the key swapped for an
+    // MT19937 keystream as long as the plaintext
+    if args[1] == "--mt" {
+        let seed: u32 = match args[2].parse() {
+            Ok(seed) => seed,
+            Err(_) => {
+                println!("{}", USAGE);
+                process::exit(1);
+            }
+        };
+        let ciphertext = mt_stream::mt_encrypt(seed, args[3].as_bytes());
+        println!("{}", cryptoutil::bytes_to_hex(&ciphertext));
+        return;
+    }
+
     let ascii_key = &args[1];
     let ascii_plaintext = &args[2];
     let ascii_key_buffer: String = ascii_key.chars().cycle().take(ascii_plaintext.len()).collect();
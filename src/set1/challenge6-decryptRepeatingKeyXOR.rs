@@ -4,36 +4,11 @@ mod cryptoutil;
 use std::fs::File;
 use std::io::{Read, BufRead, BufReader};
 use std::ops::Range;
-use std::cmp::Ordering::Less;
 use std::iter;
 use std::fmt::Write;
-use std::collections::HashMap;
 
 /// the range of key lengths to try when attempting decryption
 const ASCII_KEYSIZE_RANGE: Range<usize> = 2..40;
-const LETTER_FREQS: [(char, f64); 26]= [
-    ('a', 8.16), ('b', 1.49), ('c', 2.78), ('d', 4.25), ('e', 12.7), ('f', 2.22),
-    ('g', 2.02), ('h', 6.09), ('i', 6.97), ('j', 0.15), ('k', 0.77), ('l', 4.03),
-    ('m', 2.41), ('n', 6.75), ('o', 7.51), ('p', 1.93), ('q', 0.10), ('r', 5.99),
-    ('s', 6.44), ('t', 9.01), ('u', 2.76), ('v', 0.98), ('w', 2.36), ('x', 0.15),
-    ('y', 1.97), ('z', 0.07)
-];
-
-/// computes the hamming distance between two strings
-/// that is, this function returns the number of differing bits between
-/// the two strings
-fn hamming_distance(s: &str, t: &str) -> u32 {
-    assert_eq!(s.len(), t.len());
-    // keep a running tally of the number of ones in s ^ t
-    let mut n_ones = 0;
-    let s_bytes = s.as_bytes();
-    let t_bytes = t.as_bytes();
-    for i in 0..s.len() {
-        n_ones += (s_bytes[i] ^ t_bytes[i]).count_ones();
-    }
-    n_ones
-}
-
 fn main() {
     // read the base64-encoded ciphertext out of the file and into a buffer w/o newlines
     let file = File::open("src/set1/6.txt").expect("Failed to open input file");
@@ -44,19 +19,11 @@ fn main() {
     // convert base64 to hex
     let hex_ciphertext = cryptoutil::base64_to_hex(&b64_ciphertext);
 
-    // get the normalized hamming distance between the first two blocks for each keysize
-    let mut normalized_hds: Vec<(usize, f64)> = vec![];
-    for ascii_key_size in ASCII_KEYSIZE_RANGE {
-        // a block is key_size bytes, or key_size*2 hex digits
-        let hex_key_size = ascii_key_size*2;
-        let block1: String = hex_ciphertext.clone().chars().take(hex_key_size).collect();
-        let block2: String = hex_ciphertext.clone().chars().skip(hex_key_size).take(hex_key_size).collect();
-        let block_hd = hamming_distance(&block1, &block2);
-        normalized_hds.push((ascii_key_size, (block_hd as f64) / (ascii_key_size as f64)));
-    }
-
-    // sort the normalized hamming distances non-decreasing
-    normalized_hds.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Less));
+    // get the normalized hamming distances for each keysize, sorted non-decreasing
+    // (this used to compare the hex digits rather than the bytes they encode,
+    //  which is why it never found the right key size)
+    let ciphertext = cryptoutil::hex_to_bytes(&hex_ciphertext);
+    let normalized_hds = cryptoutil::rank_keysizes(&ciphertext, ASCII_KEYSIZE_RANGE);
 
     /*
     // let's assume that the correct keysize is in the top 3
@@ -70,103 +37,26 @@ fn main() {
     /* This removes a for loop so I can consider all the code on the same      */
     /* indentation level.                                                      */
 
-    // Break the ciphertext into blocks of key_size length using our friend
-    // the gnarly iterator.
-    // But first, pad the ciphertext so we get uniform blocks.
-    // This makes the code a lot easier to write and does not affect the
-    // scoring to the best of my knowledge.
+    // Break the ciphertext into blocks of key_size length and transpose them,
+    // so that every byte encrypted with the same key byte ends up in the same
+    // block. This used to be a pile of hex string pushing (plus padding the
+    // ciphertext with '0's to get uniform blocks); `transpose` handles a short
+    // last block on its own.
     let ascii_key_size = normalized_hds[0].0;
-    let hex_key_size = ascii_key_size * 2;
-    let len = hex_ciphertext.len();
-    let hex_ciphertext_padded = hex_ciphertext.clone() +
-                                &String::from_utf8(
-                                    vec![b'0'; hex_key_size - (len % hex_key_size)]
-                                ).unwrap();
-    let pad_len = hex_ciphertext_padded.len();
-    let blocks = (0..).take(pad_len).filter(|&x| x % hex_key_size == 0)
-                      .map(|i| &hex_ciphertext_padded[i..i+hex_key_size]);
-    // transpose the blocks
-    // there's probably some mind-blowingly elegant way to do this with iterator
-    // adapters, but I have not yet attained that level of functional programming
-    // nirvana :(
-    let mut transposed_blocks = vec![String::new(); ascii_key_size];
-    for block in blocks {
-        // split into groups of 2 (one byte each) using the gnarly iterator
-        let bytes = (0..hex_key_size).filter(|&x| x % 2 == 0).map(|i| &block[i..i+2]);
-        for (i, byte) in bytes.enumerate() {
-            transposed_blocks[i].push_str(&byte);
-        }
-    }
+    let transposed_blocks = cryptoutil::transpose(&ciphertext, ascii_key_size);
 
     // solve each block as single-character XOR
 
     // this is a list of possible most-frequent plaintext characters
     let candidates = vec![' ', 'e', 't', 'a', 'o', 'i'];
 
-    let mut hex_final_key = String::new();
-    for tb in transposed_blocks {
-        // Try every printable ascii char as a key
-        // The key that generates the plaintext with the lowest chi-square value
-        // is the one we append to the final key
-        let mut best_chi_square = ::std::f64::INFINITY;
-        let mut hex_best_key_letter = "NULL".to_string();
-        for key in 32..127 {
-            let hex_key = format!("{:02x}", key as u8);
-            let hex_key_buffer: String = hex_key.chars().cycle().take(tb.len()).collect();
-            let hex_plaintext = cryptoutil::hex_to_hex_xor(&hex_key_buffer, &tb);
-            let ascii_plaintext = cryptoutil::hex_to_ascii(&hex_plaintext);
-
-            let mut freq_table = cryptoutil::ascii_freq_analysis(&ascii_plaintext);
-            //println!("{:?}", freq_table);
-            // we're going to keep only the frequencies of lowercase letters, so
-            // add the counts of uppercase letters to the lowercase letter counts
-            // in order for the uppercase letters to still be counted
-            let mut lowercase_freq_table = HashMap::<char, u32>::new();
-            let default = 0;
-            for i in 97..123 { // 'a' to 'z' inclusive
-                let c = i as u8 as char;
-                let lowercase_freq = freq_table.get(&c).unwrap_or(&default);
-                lowercase_freq_table.insert(c, *lowercase_freq);
-            }
-
-            for i in 65..91 { // 'A' to 'Z' inclusive
-                let c = i as u8 as char;
-                let uppercase_freq = freq_table.get(&c).unwrap_or(&default);
-                let lowercase_freq = lowercase_freq_table
-                                     .get_mut(&c.to_lowercase().next().unwrap())
-                                     .unwrap();
-                *lowercase_freq += *uppercase_freq;
-            }
-            // convert the frequency table into a vector of (char, percentage),
-            // only keeping the lowercase letters
-            let mut percentages = lowercase_freq_table.into_iter()
-                //.inspect(|&(l, c)| println!("{}: {}", l, c))
-                .map(|(letter, count)| (letter, (count as f64) / (tb.len() as f64)))
-                .collect::<Vec<(char, f64)>>();
-            percentages.sort_by(|a, b| a.0.cmp(&b.0));
-            //println!("{:?}", percentages);
-            // google suggests that the standard way to measure the correlation of
-            // two histograms is the chi-square test
-            // I have no idea if I'm implementing this correctly
-
-            // I could have used the `fold` iterator consumer here, but I think
-            // it would have become too messy
-            let mut chi_square = 0.0;
-            for (observed, expected) in percentages.into_iter().zip(LETTER_FREQS.iter()) {
-                // we should be comparing stats for the same letter
-                assert_eq!(observed.0, expected.0);
-                let (o_value, e_value) = (observed.1, expected.1);
-                chi_square += (o_value - e_value) * (o_value - e_value) / e_value;
-            }
-
-            if chi_square < best_chi_square {
-                best_chi_square = chi_square;
-                hex_best_key_letter = hex_key.clone();
-            }
-        }
-
-        hex_final_key.push_str(&hex_best_key_letter.clone());
-    }
+    // Try every possible key byte on each block, and keep the one whose plaintext
+    // looks the most like english. This is the same search challenge 3 needed, so
+    // it lives in cryptoutil now.
+    let key: Vec<u8> = transposed_blocks.iter()
+                                        .map(|tb| cryptoutil::break_single_byte_xor(tb).0)
+                                        .collect();
+    let hex_final_key = cryptoutil::bytes_to_hex(&key);
 
     // decrypt the full message with our derived key!
     // this code is more or less straight out of challenge 1-5
@@ -0,0 +1,51 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../ecb_detect.rs"]
+mod ecb_detect;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufRead};
+use std::process;
+
+/// the number of top-scoring lines to print
+const SHOW_TOP: usize = 5;
+
+// Same idea as challenge 4: one hex-encoded ciphertext per line, and we want to
+// find the odd one out. Here the odd one out is the one with repeated blocks.
+fn main() {
+    // the challenge file is the default, but any file of hex lines will do. It
+    // isn't checked in (fetch it from cryptopals.com/static/challenge-data/8.txt)
+    let USAGE = "usage: challenge8_detectECB [file of hex ciphertexts, default src/set1/8.txt]";
+    let args = env::args().collect::<Vec<_>>();
+    let path = args.get(1).map(|s| s.as_str()).unwrap_or("src/set1/8.txt");
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Failed to open {}: {}", path, e);
+            println!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+
+    let ciphertexts: Vec<Vec<u8>> = BufReader::new(&file).lines()
+        .map(|line| cryptoutil::hex_to_bytes(line.expect("Invalid line").trim()))
+        .collect();
+
+    let ranked = ecb_detect::rank_ecb(&ciphertexts, &ecb_detect::COMMON_BLOCK_SIZES);
+    for &(i, block_size, duplicates) in ranked.iter().take(SHOW_TOP) {
+        if duplicates == 0 {
+            break;
+        }
+        println!("Ciphertext #{} has {} repeated {}-byte blocks", i+1, duplicates, block_size);
+    }
+
+    match ranked.first() {
+        Some(&(i, block_size, duplicates)) if duplicates > 0 =>
+            println!("Ciphertext #{} was most likely encrypted with ECB ({}-byte blocks)",
+                     i+1, block_size),
+        _ => println!("None of the ciphertexts look like ECB"),
+    }
+}
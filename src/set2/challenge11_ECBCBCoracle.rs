@@ -0,0 +1,41 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../ecb_detect.rs"]
+mod ecb_detect;
+
+use oracle::{Oracle, Mode};
+
+const TRIALS: usize = 100;
+
+fn main() {
+    // real randomness this time, since the point is that we can't predict the mode
+    let os_random = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let mut oracle = oracle::EcbOrCbcOracle::new(os_random);
+
+    // The oracle adds 5-10 bytes to the front, so 11 bytes of our own finish off
+    // the first block, and anything after that is entirely under our control.
+    // Two identical blocks of our own will encrypt identically under ECB.
+    let plaintext = vec![b'A'; 11 + 2 * aes::BLOCK_SIZE];
+
+    let mut correct = 0;
+    for _ in 0..TRIALS {
+        let ciphertext = oracle.encrypt(&plaintext);
+        let guess = if ecb_detect::is_ecb(&ciphertext, aes::BLOCK_SIZE) { Mode::Ecb } else { Mode::Cbc };
+        if Some(guess) == oracle.last_mode() {
+            correct += 1;
+        }
+    }
+    println!("detected the mode correctly {}/{} times", correct, TRIALS);
+}
@@ -0,0 +1,38 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../ecb_detect.rs"]
+mod ecb_detect;
+#[path="../ecb_attack.rs"]
+mod ecb_attack;
+
+use std::io::{self, Write};
+
+const B64_SECRET: &'static str =
+    "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK";
+
+fn main() {
+    let secret = cryptoutil::base64_to_bytes(B64_SECRET);
+    let mut oracle = oracle::EcbAppendingOracle::new(rng::XorShift::new(12), &secret);
+
+    let profile = ecb_attack::profile(&mut oracle).expect("oracle isn't using ECB");
+    println!("{:?}", profile);
+
+    let recovered = ecb_attack::recover_suffix(&mut oracle, &profile, |so_far, total| {
+        print!("\r{}/{} bytes", so_far.len(), total);
+        io::stdout().flush().unwrap();
+    });
+    println!("\n{}", String::from_utf8_lossy(&recovered));
+    assert_eq!(recovered, secret);
+}
@@ -0,0 +1,74 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../forgery.rs"]
+mod forgery;
+
+use std::collections::HashMap;
+use oracle::Oracle;
+use rng::RandomSource;
+
+/// parses a string like "foo=bar&baz=qux" into a map
+fn parse_kv(s: &str) -> HashMap<String, String> {
+    s.split('&')
+     .filter_map(|pair| {
+         let mut it = pair.splitn(2, '=');
+         match (it.next(), it.next()) {
+             (Some(k), Some(v)) => Some((k.to_string(), v.to_string())),
+             _ => None,
+         }
+     })
+     .collect()
+}
+
+/// encodes a user profile for <email>, eating any metacharacters
+fn profile_for(email: &[u8]) -> Vec<u8> {
+    let email: Vec<u8> = email.iter().cloned().filter(|&c| c != b'&' && c != b'=').collect();
+    let mut profile = b"email=".to_vec();
+    profile.extend(email);
+    profile.extend_from_slice(b"&uid=10&role=user");
+    profile
+}
+
+fn main() {
+    let cipher = aes::Aes::new(&rng::XorShift::new(13).gen_bytes(16));
+    let bs = aes::BLOCK_SIZE;
+    let mut oracle = oracle::FnOracle::new()
+        .with_encrypt(|email| modes::ecb_encrypt(&cipher, &padding::pkcs7_pad(&profile_for(email), bs)))
+        .with_decrypt(|ciphertext| {
+            padding::pkcs7_unpad(&modes::ecb_decrypt(&cipher, ciphertext), bs).ok()
+        });
+
+    // first, get a block that's just "admin" and its padding, by lining it up
+    // right after "email="
+    let (input, admin_index) = forgery::block_aligned_input("email=".len(), bs,
+                                                            &padding::pkcs7_pad(b"admin", bs));
+    let admin_ct = oracle.encrypt(&input);
+
+    // then pick an email long enough that "role=" ends a block, so that "user"
+    // (plus padding) is the whole last block, and swap our admin block in for it
+    let before_role = "email=".len() + "&uid=10&role=".len();
+    let domain = b"@bar.com";
+    let mut email = vec![b'a'; forgery::filler_to_boundary(before_role, domain.len(), bs)];
+    email.extend_from_slice(domain);
+    let user_ct = oracle.encrypt(&email);
+    let keep = (before_role + email.len()) / bs;
+
+    let forged = forgery::ecb_splice(&[(&user_ct, 0, keep), (&admin_ct, admin_index, 1)], bs);
+    let profile = oracle.decrypt(&forged).expect("forged ciphertext has bad padding");
+    let profile = String::from_utf8_lossy(&profile).into_owned();
+    println!("{}", profile);
+    println!("{:?}", parse_kv(&profile));
+    assert_eq!(parse_kv(&profile).get("role").map(|s| s.as_str()), Some("admin"));
+}
@@ -0,0 +1,19 @@
+#![allow(non_snake_case)]
+
+#[path="../padding.rs"]
+mod padding;
+
+fn main() {
+    let candidates: [&[u8]; 3] = [
+        b"ICE ICE BABY\x04\x04\x04\x04",
+        b"ICE ICE BABY\x05\x05\x05\x05",
+        b"ICE ICE BABY\x01\x02\x03\x04",
+    ];
+    for candidate in candidates.iter() {
+        match padding::pkcs7_unpad(candidate, 16) {
+            Ok(v) => println!("{:?} => {:?}", String::from_utf8_lossy(candidate),
+                                              String::from_utf8_lossy(&v)),
+            Err(e) => println!("{:?} => invalid: {}", String::from_utf8_lossy(candidate), e),
+        }
+    }
+}
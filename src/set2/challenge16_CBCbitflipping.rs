@@ -0,0 +1,55 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../forgery.rs"]
+mod forgery;
+
+use oracle::Oracle;
+
+const PREFIX: &'static [u8] = b"comment1=cooking%20MCs;userdata=";
+const SUFFIX: &'static [u8] = b";comment2=%20like%20a%20pound%20of%20bacon";
+
+/// returns true if the decrypted cookie has an admin=true field
+fn is_admin(plaintext: &[u8]) -> bool {
+    plaintext.split(|&c| c == b';').any(|field| field == b"admin=true")
+}
+
+fn main() {
+    let mut cbc = oracle::CbcOracle::with_affixes(rng::XorShift::new(16), PREFIX, SUFFIX);
+    let bs = aes::BLOCK_SIZE;
+
+    // the oracle quotes out ';' and '=' so we can't just ask for admin=true
+    let ciphertext = {
+        let mut quoting = oracle::FnOracle::new().with_encrypt(|userdata| {
+            let quoted: Vec<u8> = userdata.iter().flat_map(|&c| match c {
+                b';' => b"%3B".to_vec(),
+                b'=' => b"%3D".to_vec(),
+                c => vec![c],
+            }).collect();
+            cbc.encrypt(&quoted)
+        });
+        // one block to sacrifice, then a block with stand-ins for ; and =
+        let (input, _) = forgery::block_aligned_input(PREFIX.len(), bs, &[b'A'; 16]);
+        let mut input = input;
+        input.extend_from_slice(b"XadminXtrueX");
+        quoting.encrypt(&input)
+    };
+
+    let offset = PREFIX.len() + bs;
+    let forged = forgery::cbc_bitflip(&ciphertext, offset, b"XadminXtrueX", b";admin=true;");
+    let plaintext = cbc.decrypt(&forged).expect("forged ciphertext has bad padding");
+    println!("{}", String::from_utf8_lossy(&plaintext));
+    println!("admin: {}", is_admin(&plaintext));
+    assert!(is_admin(&plaintext));
+}
@@ -0,0 +1,10 @@
+#![allow(non_snake_case)]
+
+#[path="../padding.rs"]
+mod padding;
+
+fn main() {
+    let padded = padding::pkcs7_pad(b"YELLOW SUBMARINE", 20);
+    println!("{:?}", String::from_utf8_lossy(&padded));
+    assert_eq!(padded, b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec());
+}
@@ -0,0 +1,54 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../padding_oracle.rs"]
+mod padding_oracle;
+
+use oracle::Oracle;
+use padding_oracle::PaddingOracleAttack;
+
+const B64_PLAINTEXTS: [&'static str; 10] = [
+    "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+    "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+    "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+    "MDAwMDAzQ29va2luZyBNQydzIGxpa2UgYSBwb3VuZCBvZiBiYWNvbg==",
+    "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+    "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbA==",
+    "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+    "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+    "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+    "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+];
+
+fn main() {
+    let mut oracle = oracle::CbcOracle::new(rng::XorShift::new(17));
+
+    for b64_plaintext in B64_PLAINTEXTS.iter() {
+        let plaintext = cryptoutil::base64_to_bytes(b64_plaintext);
+        let ciphertext = oracle.encrypt(&plaintext);
+
+        let result = padding_oracle::padding_oracle_decrypt(&mut oracle, aes::BLOCK_SIZE, &ciphertext)
+                                    .expect("recovered plaintext has bad padding");
+        println!("{} ({} queries)", result.to_ascii(), result.queries);
+        assert_eq!(result.plaintext, plaintext);
+    }
+
+    // the same trick run backwards encrypts whatever we like
+    let forged = {
+        let mut attack = PaddingOracleAttack::new(&mut oracle, aes::BLOCK_SIZE);
+        attack.forge(b"forged without ever seeing the key")
+    };
+    println!("forged ciphertext decrypts to: {}",
+             String::from_utf8_lossy(&oracle.decrypt(&forged).unwrap()));
+}
@@ -0,0 +1,21 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+
+fn main() {
+    let b64_ciphertext = "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==";
+    let ciphertext = cryptoutil::hex_to_bytes(&cryptoutil::base64_to_hex(b64_ciphertext));
+    let cipher = aes::Aes::new(b"YELLOW SUBMARINE");
+
+    let plaintext = modes::ctr_decrypt(&cipher, 0, &ciphertext);
+    println!("{}", String::from_utf8_lossy(&plaintext));
+
+    // and since CTR is just a stream cipher, encrypting the plaintext again should
+    // get us right back where we started
+    assert_eq!(modes::ctr_encrypt(&cipher, 0, &plaintext), ciphertext);
+}
@@ -0,0 +1,71 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../rng.rs"]
+mod rng;
+#[path="../fixed_nonce.rs"]
+mod fixed_nonce;
+
+use rng::RandomSource;
+
+/// the first stanzas of Easter, 1916 (the challenge gives these base64-encoded)
+const LINES: [&'static str; 40] = [
+    "I have met them at close of day", "Coming with vivid faces",
+    "From counter or desk among grey", "Eighteenth-century houses.",
+    "I have passed with a nod of the head", "Or polite meaningless words,",
+    "Or have lingered awhile and said", "Polite meaningless words,",
+    "And thought before I had done", "Of a mocking tale or a gibe",
+    "To please a companion", "Around the fire at the club,",
+    "Being certain that they and I", "But lived where motley is worn:",
+    "All changed, changed utterly:", "A terrible beauty is born.",
+    "That woman's days were spent", "In ignorant good will,",
+    "Her nights in argument", "Until her voice grew shrill.",
+    "What voice more sweet than hers", "When young and beautiful,",
+    "She rode to harriers?", "This man had kept a school",
+    "And rode our winged horse.", "This other his helper and friend",
+    "Was coming into his force;", "He might have won fame in the end,",
+    "So sensitive his nature seemed,", "So daring and sweet his thought.",
+    "This other man I had dreamed", "A drunken, vain-glorious lout.",
+    "He had done most bitter wrong", "To some who are near my heart,",
+    "Yet I number him in the song;", "He, too, has resigned his part",
+    "In the casual comedy;", "He, too, has been changed in his turn,",
+    "Transformed utterly:", "A terrible beauty is born.",
+];
+
+fn print_all(breaker: &fixed_nonce::FixedNonceBreaker) {
+    for (i, plaintext) in breaker.plaintexts().iter().enumerate() {
+        println!("{:2} {}", i, String::from_utf8_lossy(plaintext));
+    }
+}
+
+fn main() {
+    let cipher = aes::Aes::new(&rng::XorShift::new(19).gen_bytes(16));
+    let ciphertexts: Vec<Vec<u8>> = LINES.iter()
+                                         .map(|line| modes::ctr_encrypt(&cipher, 0, line.as_bytes()))
+                                         .collect();
+
+    let mut breaker = fixed_nonce::FixedNonceBreaker::ragged(&ciphertexts);
+    println!("straight out of the statistics:");
+    print_all(&breaker);
+
+    // every line starts with a capital, which the statistics don't expect, and
+    // line 0 is pretty obviously "I have met them..."
+    breaker.correct(0, 0, b"I");
+
+    // the last few bytes only show up in one or two lines, so the statistics
+    // can't do much with them either. The longest line is pretty obviously
+    // "He, too, has been changed in his turn,"
+    let longest = (0..ciphertexts.len()).max_by_key(|&i| ciphertexts[i].len()).unwrap();
+    let offset = LINES[longest].len() - "in his turn,".len();
+    breaker.correct(longest, offset, b"in his turn,");
+    println!("\nafter fixing up the first column and the end of line {}:", longest);
+    print_all(&breaker);
+
+    let wrong = (0..LINES.len()).filter(|&i| breaker.plaintext(i) != LINES[i].as_bytes()).count();
+    println!("\n{} of {} lines still wrong", wrong, LINES.len());
+}
@@ -0,0 +1,17 @@
+#![allow(non_snake_case)]
+
+#[path="../mt19937.rs"]
+mod mt19937;
+
+fn main() {
+    // the reference implementation's default seed, whose outputs are well known
+    let mut mt = mt19937::Mt19937::new(5489);
+    let first: Vec<u32> = (0..5).map(|_| mt.next_u32()).collect();
+    println!("32-bit: {:?}", first);
+    assert_eq!(first[0], 3499211612);
+
+    let mut mt = mt19937::Mt19937_64::new(5489);
+    let first: Vec<u64> = (0..5).map(|_| mt.next_u64()).collect();
+    println!("64-bit: {:?}", first);
+    assert_eq!(first[0], 14514284786278117030);
+}
@@ -0,0 +1,26 @@
+#![allow(non_snake_case)]
+
+#[path="../rng.rs"]
+mod rng;
+#[path="../mt19937.rs"]
+mod mt19937;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use rng::RandomSource;
+
+fn main() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32;
+
+    // rather than actually sleeping for 40-1000 seconds before and after seeding,
+    // pretend we did by shifting the clock
+    let mut rng = rng::XorShift::new(now as u64);
+    let seeded_at = now + 40 + rng.gen_range(961) as u32;
+    let output = mt19937::Mt19937::new(seeded_at).next_u32();
+    let now = seeded_at + 40 + rng.gen_range(961) as u32;
+    println!("first output: {}", output);
+
+    // it must have been seeded sometime in the last (say) hour
+    let seed = mt19937::crack_timestamp_seed(&[output], now, 3600).expect("failed to find the seed");
+    println!("seed: {} (actually {})", seed, seeded_at);
+    assert_eq!(seed, seeded_at);
+}
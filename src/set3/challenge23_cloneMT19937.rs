@@ -0,0 +1,26 @@
+#![allow(non_snake_case)]
+
+#[path="../mt19937.rs"]
+mod mt19937;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    let mut original = mt19937::Mt19937::new(seed as u32);
+    let outputs: Vec<u32> = (0..mt19937::STATE_WORDS).map(|_| original.next_u32()).collect();
+    let mut clone = mt19937::clone_from_outputs(&outputs).unwrap();
+    for _ in 0..10000 {
+        assert_eq!(clone.next_u32(), original.next_u32());
+    }
+    println!("32-bit clone matches the next 10000 outputs");
+
+    let mut original = mt19937::Mt19937_64::new(seed);
+    let outputs: Vec<u64> = (0..mt19937::STATE_WORDS_64).map(|_| original.next_u64()).collect();
+    let mut clone = mt19937::clone_from_outputs_64(&outputs).unwrap();
+    for _ in 0..10000 {
+        assert_eq!(clone.next_u64(), original.next_u64());
+    }
+    println!("64-bit clone matches the next 10000 outputs");
+}
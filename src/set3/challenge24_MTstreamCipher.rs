@@ -0,0 +1,43 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../rng.rs"]
+mod rng;
+#[path="../mt19937.rs"]
+mod mt19937;
+#[path="../mt_stream.rs"]
+mod mt_stream;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use rng::RandomSource;
+
+fn main() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32;
+    let mut rng = rng::XorShift::new(now as u64);
+
+    // a random prefix, followed by 14 'A's we chose
+    let seed = rng.gen_range(0x10000) as u16;
+    let prefix_len = 5 + rng.gen_range(20);
+    let mut plaintext = rng.gen_bytes(prefix_len);
+    plaintext.extend_from_slice(&[b'A'; 14]);
+    let ciphertext = mt_stream::mt_encrypt(seed as u32, &plaintext);
+    assert_eq!(mt_stream::mt_decrypt(seed as u32, &ciphertext), plaintext);
+
+    let recovered = mt_stream::recover_seed_16(&ciphertext, ciphertext.len() - 14, &[b'A'; 14])
+                             .expect("failed to recover the seed");
+    println!("seed: {} (actually {})", recovered, seed);
+    assert_eq!(recovered, seed);
+
+    // a token seeded from the clock a few minutes ago, and one that wasn't
+    let issued_at = now - rng.gen_range(600) as u32;
+    let time_token = mt_stream::reset_token(issued_at, 16);
+    let random_token = rng.gen_bytes(16);
+    for token in &[time_token, random_token] {
+        match mt_stream::time_seeded_token(token, now, 3600) {
+            Some(t) => println!("{} was seeded with the time {} seconds ago",
+                                cryptoutil::bytes_to_hex(token), now - t),
+            None => println!("{} doesn't look time-seeded", cryptoutil::bytes_to_hex(token)),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../ctr_edit_attack.rs"]
+mod ctr_edit_attack;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use oracle::Oracle;
+
+fn main() {
+    // the challenge uses the plaintext from challenge 7, which is the same lyrics
+    // challenge 6 decrypts, so just decrypt those again
+    let file = File::open("src/set1/6.txt").expect("Failed to open input file");
+    let b64 = BufReader::new(&file).lines().fold(String::new(), |s, x| s + &x.expect("Error reading input"));
+    let secret = cryptoutil::repeating_key_xor(&cryptoutil::base64_to_bytes(&b64),
+                                               b"Terminator X: Bring the noise");
+
+    let mut oracle = oracle::CtrEditOracle::new(rng::XorShift::new(25));
+    let ciphertext = oracle.encrypt(&secret);
+
+    let plaintext = ctr_edit_attack::recover_plaintext(&mut oracle, &ciphertext);
+    println!("{}", String::from_utf8_lossy(&plaintext));
+    assert_eq!(plaintext, secret);
+}
@@ -0,0 +1,41 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../oracle.rs"]
+mod oracle;
+#[path="../cbc_key_iv_attack.rs"]
+mod cbc_key_iv_attack;
+
+use oracle::Oracle;
+
+fn main() {
+    let mut oracle = oracle::CbcKeyAsIvOracle::new(rng::XorShift::new(27));
+    let message = b"comment1=cooking%20MCs;userdata=hello;comment2=%20like%20a%20pound%20of%20bacon";
+    let ciphertext = oracle.encrypt(message);
+
+    // the receiver is happy with the real thing...
+    println!("real message: {:?}", oracle.receive(&ciphertext));
+
+    // ...but not with our probe, and it tells us why
+    let probe = cbc_key_iv_attack::forge_probe(&ciphertext, aes::BLOCK_SIZE).unwrap();
+    if let Err(e) = oracle.receive(&probe) {
+        println!("probe: {}", e);
+    }
+
+    let key = cbc_key_iv_attack::recover_key(&mut oracle, &ciphertext).expect("failed to recover the key");
+    println!("recovered key: {}", cryptoutil::bytes_to_hex(&key));
+    println!("actual key:    {}", cryptoutil::bytes_to_hex(oracle.key()));
+
+    let plaintext = padding::pkcs7_unpad(&modes::cbc_key_as_iv_decrypt(&key, &ciphertext), aes::BLOCK_SIZE).unwrap();
+    println!("{}", String::from_utf8_lossy(&plaintext));
+    assert_eq!(&plaintext[..], &message[..]);
+}
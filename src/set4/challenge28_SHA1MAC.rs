@@ -0,0 +1,28 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+
+fn main() {
+    // make sure the hash itself is right first (FIPS 180 examples)
+    assert_eq!(sha1::sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(sha1::sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+
+    let key = b"YELLOW SUBMARINE";
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let mac = sha1::secret_prefix_mac(key, message);
+    println!("MAC: {}", cryptoutil::bytes_to_hex(&mac));
+
+    // changing the message (or not knowing the key) gets a different MAC
+    let mut tampered = message.to_vec();
+    tampered[32] ^= 1;
+    assert!(sha1::secret_prefix_mac(key, &tampered) != mac);
+    assert!(sha1::secret_prefix_mac(b"yellow submarine", message) != mac);
+    println!("tampered message and wrong key both rejected");
+}
@@ -0,0 +1,36 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../length_extension.rs"]
+mod length_extension;
+
+use rng::RandomSource;
+
+fn main() {
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let key_len = 1 + rng.gen_range(32);
+    let key = rng.gen_bytes(key_len);
+
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let mac = sha1::secret_prefix_mac(&key, message);
+
+    // we don't know the key, so guess its length until the server accepts the
+    // forged message
+    let forgery = length_extension::length_extension_attack::<sha1::Sha1, _>(
+        &mac, message, b";admin=true", length_extension::SECRET_LEN_RANGE,
+        |m, mac| sha1::secret_prefix_mac(&key, m) == mac
+    ).expect("no key length worked");
+
+    println!("key length: {} (actually {})", forgery.secret_len, key_len);
+    println!("message: {}", String::from_utf8_lossy(&forgery.message));
+    println!("MAC: {}", cryptoutil::bytes_to_hex(&forgery.mac));
+}
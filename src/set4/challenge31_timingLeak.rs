@@ -0,0 +1,50 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../hmac.rs"]
+mod hmac;
+#[path="../timing_leak.rs"]
+mod timing_leak;
+
+use std::env;
+use std::time::{Duration, Instant};
+use hash::Hash;
+use rng::RandomSource;
+
+/// usage: challenge31_timingLeak [per-byte delay in microseconds] [samples per guess]
+/// the challenge's 50ms delay works, but takes hours; challenge 32's 5ms takes a
+/// good while too. The default is enough to show the leak in a few seconds.
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let delay_us = args.get(1).map(|s| s.parse().expect("delay must be a number")).unwrap_or(50);
+    let mut config = timing_leak::TimingAttackConfig::default();
+    if let Some(samples) = args.get(2) {
+        config.samples = samples.parse().expect("samples must be a number");
+    }
+
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let server = timing_leak::HmacVerifier::<sha1::Sha1>::new(&rng.gen_bytes(16), Duration::from_micros(delay_us));
+    let file = b"foo";
+
+    let start = Instant::now();
+    let mac = timing_leak::recover_mac(sha1::Sha1::DIGEST_SIZE, &config,
+                                       |signature| server.verify(file, signature),
+                                       |so_far| println!("{}", cryptoutil::bytes_to_hex(so_far)));
+    println!("took {:?}", start.elapsed());
+    match mac {
+        Some(mac) => {
+            println!("recovered: {}", cryptoutil::bytes_to_hex(&mac));
+            println!("expected:  {}", cryptoutil::bytes_to_hex(&server.expected(file)));
+        }
+        None => println!("the timing was too noisy; try a longer delay or more samples"),
+    }
+}
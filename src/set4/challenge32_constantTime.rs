@@ -0,0 +1,50 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../hmac.rs"]
+mod hmac;
+#[path="../timing_leak.rs"]
+mod timing_leak;
+
+use std::time::Duration;
+use hash::Hash;
+use rng::RandomSource;
+
+const SAMPLES: usize = 20000;
+
+fn report(name: &str, c: &timing_leak::TimingComparison) {
+    println!("{}: {:.0}ns vs {:.0}ns, t = {:.1} -> {}", name, c.mean.0, c.mean.1, c.t,
+             if c.leaks() { "leaks" } else { "no leak detected" });
+}
+
+fn main() {
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let secret = rng.gen_bytes(sha1::Sha1::DIGEST_SIZE);
+
+    // challenge 32 shrinks the delay to 5ms; here it's a microsecond, and still
+    // shows up plain as day
+    let insecure = timing_leak::measure_compare(&secret, SAMPLES, |a, b| {
+        timing_leak::insecure_compare(a, b, Duration::from_micros(1))
+    });
+    report("insecure_compare (1us per byte)", &insecure);
+    let secure = timing_leak::measure_compare(&secret, SAMPLES, |a, b| cryptoutil::constant_time_eq(a, b));
+    report("constant_time_eq", &secure);
+
+    // and the attack that breaks the insecure verifier gets nowhere against one
+    // that compares in constant time
+    let server = timing_leak::HmacVerifier::<sha1::Sha1>::secure(&rng.gen_bytes(16));
+    let config = timing_leak::TimingAttackConfig { samples: 3, retries: 0, max_backtracks: 2 };
+    let mac = timing_leak::recover_mac(sha1::Sha1::DIGEST_SIZE, &config,
+                                       |signature| server.verify(b"foo", signature), |_| {});
+    println!("attack on the constant-time verifier: {}",
+             if mac.is_some() { "succeeded (!)" } else { "failed" });
+}
@@ -0,0 +1,42 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../bignum.rs"]
+mod bignum;
+#[path="../dh.rs"]
+mod dh;
+
+use bignum::BigUint;
+
+fn main() {
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+
+    // the toy version first, where everything fits in a machine word
+    let toy = dh::Group::new(BigUint::from_u64(37), BigUint::from_u64(5));
+    let (a, b) = (toy.keypair(&mut rng), toy.keypair(&mut rng));
+    let s = a.shared_secret(&toy, b.public());
+    assert_eq!(s, b.shared_secret(&toy, a.public()));
+    println!("p = 37, g = 5: A = {}, B = {}, s = {}", a.public(), b.public(), s);
+
+    // then the real groups
+    for &(name, ref group) in &[("1536", dh::Group::modp_1536()),
+                                ("2048", dh::Group::modp_2048()),
+                                ("3072", dh::Group::modp_3072())] {
+        let (a, b) = (group.keypair(&mut rng), group.keypair(&mut rng));
+        // send the public keys over as hex, the way they'd go over the wire
+        let (a_hex, b_hex) = (a.public().to_hex(), b.public().to_hex());
+        let s_a = a.shared_secret(group, &BigUint::from_hex(&b_hex));
+        let s_b = b.shared_secret(group, &BigUint::from_hex(&a_hex));
+        assert_eq!(s_a, s_b);
+        println!("{}-bit group: session key {}", name, cryptoutil::bytes_to_hex(&dh::session_key(&s_a)));
+    }
+}
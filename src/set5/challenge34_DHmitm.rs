@@ -0,0 +1,60 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../bignum.rs"]
+mod bignum;
+#[path="../dh.rs"]
+mod dh;
+#[path="../dh_mitm.rs"]
+mod dh_mitm;
+
+use dh_mitm::{Channel, Direction};
+
+const MESSAGES: [&[u8]; 3] = [
+    b"Hi Bob, it's Alice",
+    b"Let's meet at the usual place",
+    b"Don't tell anyone",
+];
+
+/// prints what the attacker read, and checks it's everything that was said
+fn report(intercepted: &dh_mitm::Intercepted) {
+    for &(direction, ref message) in &intercepted.messages {
+        let arrow = if direction == Direction::AliceToBob { "A -> B" } else { "B -> A" };
+        println!("  {}: {}", arrow, String::from_utf8_lossy(message));
+    }
+    assert_eq!(intercepted.messages.len(), 2 * MESSAGES.len());
+}
+
+fn main() {
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let group = dh::Group::modp_1536();
+
+    // with nobody in the middle, the messages get through
+    let mut channel = Channel::new(dh_mitm::Wire);
+    let received = dh_mitm::run_exchange(&mut channel, &group, &MESSAGES, &mut rng)
+        .expect("honest exchange failed");
+    assert_eq!(received, MESSAGES.iter().map(|m| m.to_vec()).collect::<Vec<_>>());
+    println!("honest wire: Bob got all {} messages", received.len());
+
+    // and with the attacker swapping in p for both public keys, they still do,
+    // but the attacker reads every one of them on the way past
+    let mut channel = Channel::new(dh_mitm::PublicKeyInjector::new());
+    dh_mitm::run_exchange(&mut channel, &group, &MESSAGES, &mut rng).expect("attacked exchange failed");
+    println!("public keys replaced with p; the attacker read:");
+    report(&channel.node().intercepted);
+}
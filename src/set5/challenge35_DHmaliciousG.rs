@@ -0,0 +1,58 @@
+#![allow(non_snake_case)]
+
+#[path="../cryptoutil.rs"]
+mod cryptoutil;
+#[path="../aes.rs"]
+mod aes;
+#[path="../modes.rs"]
+mod modes;
+#[path="../padding.rs"]
+mod padding;
+#[path="../rng.rs"]
+mod rng;
+#[path="../md_padding.rs"]
+mod md_padding;
+#[path="../hash.rs"]
+mod hash;
+#[path="../sha1.rs"]
+mod sha1;
+#[path="../bignum.rs"]
+mod bignum;
+#[path="../dh.rs"]
+mod dh;
+#[path="../dh_mitm.rs"]
+mod dh_mitm;
+
+use dh_mitm::{Channel, Direction};
+
+const MESSAGES: [&[u8]; 3] = [
+    b"Hi Bob, it's Alice",
+    b"Let's meet at the usual place",
+    b"Don't tell anyone",
+];
+
+/// prints what the attacker read, and checks it's everything that was said
+fn report(intercepted: &dh_mitm::Intercepted) {
+    for &(direction, ref message) in &intercepted.messages {
+        let arrow = if direction == Direction::AliceToBob { "A -> B" } else { "B -> A" };
+        println!("  {}: {}", arrow, String::from_utf8_lossy(message));
+    }
+    assert_eq!(intercepted.messages.len(), 2 * MESSAGES.len());
+}
+
+fn main() {
+    let mut rng = rng::OsRandom::new().expect("failed to open /dev/urandom");
+    let group = dh::Group::modp_1536();
+
+    for &generator in &[dh_mitm::MaliciousGenerator::One,
+                        dh_mitm::MaliciousGenerator::P,
+                        dh_mitm::MaliciousGenerator::PMinusOne] {
+        let mut channel = Channel::new(dh_mitm::GeneratorInjector::new(generator));
+        let received = dh_mitm::run_exchange(&mut channel, &group, &MESSAGES, &mut rng)
+            .expect("attacked exchange failed");
+        // the endpoints got their messages through, none the wiser
+        assert_eq!(received.len(), MESSAGES.len());
+        println!("g replaced with {:?}; the attacker read:", generator);
+        report(&channel.node().intercepted);
+    }
+}
@@ -0,0 +1,185 @@
+// SHA-1 (FIPS 180-4).
+// The state can be exported and picked back up again. A digest *is* the state
+// after the last block, so given the SHA-1 of some unknown message and its length,
+// we can carry on hashing from where it stopped (see `Sha1::resume`).
+
+use cryptoutil;
+use hash::{Hash, MdHash};
+use md_padding::{self, Endian};
+
+/// the size of a digest in bytes
+pub const DIGEST_SIZE: usize = 20;
+
+const INITIAL_STATE: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+/// an in-progress SHA-1 computation
+#[derive(Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    /// bytes that don't make up a whole block yet
+    buffer: Vec<u8>,
+    /// the number of bytes hashed so far
+    length: u64,
+}
+
+impl Sha1 {
+    pub fn new() -> Sha1 {
+        Sha1 { state: INITIAL_STATE, buffer: vec![], length: 0 }
+    }
+
+    /// picks up hashing right after the message <digest> is the hash of, which
+    /// was <length> bytes long *including* its padding
+    /// (so <length> must be a whole number of blocks)
+    pub fn resume(digest: &[u8], length: u64) -> Sha1 {
+        assert_eq!(digest.len(), DIGEST_SIZE);
+        assert!(length % md_padding::BLOCK_SIZE as u64 == 0, "resumed length must be a whole number of blocks");
+        let mut state = [0u32; 5];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = md_padding::read_u32(&digest[4 * i..], Endian::Big);
+        }
+        Sha1 { state: state, buffer: vec![], length: length }
+    }
+
+    /// the number of bytes hashed so far
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// hashes <data>
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        let whole = self.buffer.len() / md_padding::BLOCK_SIZE * md_padding::BLOCK_SIZE;
+        let blocks: Vec<u8> = self.buffer.drain(..whole).collect();
+        for block in blocks.chunks(md_padding::BLOCK_SIZE) {
+            self.compress(block);
+        }
+    }
+
+    /// pads the message and returns the digest
+    pub fn finalize(mut self) -> Vec<u8> {
+        let padding = md_padding::md_padding(self.length, Endian::Big);
+        self.update(&padding);
+        self.state.iter().flat_map(|&w| md_padding::u32_bytes(w, Endian::Big)).collect()
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = md_padding::read_u32(&block[4 * i..], Endian::Big);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for i in 0..80 {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(w[i]);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (s, v) in self.state.iter_mut().zip(&[a, b, c, d, e]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+}
+
+impl Hash for Sha1 {
+    const DIGEST_SIZE: usize = DIGEST_SIZE;
+    const BLOCK_SIZE: usize = md_padding::BLOCK_SIZE;
+
+    fn new() -> Sha1 {
+        Sha1::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha1::update(self, data)
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Sha1::finalize(self)
+    }
+}
+
+impl MdHash for Sha1 {
+    const ENDIAN: Endian = Endian::Big;
+
+    fn resume(digest: &[u8], length: u64) -> Sha1 {
+        Sha1::resume(digest, length)
+    }
+}
+
+/// returns the SHA-1 digest of <data>
+pub fn sha1(data: &[u8]) -> Vec<u8> {
+    let mut h = Sha1::new();
+    h.update(data);
+    h.finalize()
+}
+
+/// returns the SHA-1 digest of <data> as hex
+pub fn sha1_hex(data: &[u8]) -> String {
+    cryptoutil::bytes_to_hex(&sha1(data))
+}
+
+/// from challenge 4-28
+/// a (broken) MAC: SHA-1(key || message)
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut h = Sha1::new();
+    h.update(key);
+    h.update(message);
+    h.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FIPS 180 examples
+    #[test]
+    fn known_digests() {
+        assert_eq!(sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(sha1_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+                   "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+        assert_eq!(sha1_hex(&vec![b'a'; 1000000]), "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for split in 0..data.len() {
+            let mut h = Sha1::new();
+            h.update(&data[..split]);
+            h.update(&data[split..]);
+            assert_eq!(h.length(), data.len() as u64);
+            assert_eq!(h.finalize(), sha1(&data));
+        }
+    }
+
+    #[test]
+    fn resumes_after_padding() {
+        let message = b"a message";
+        let digest = sha1(message);
+        let glue = md_padding::md_padding(message.len() as u64, Endian::Big);
+        let mut h = Sha1::resume(&digest, (message.len() + glue.len()) as u64);
+        h.update(b"and more");
+        let mut full = message.to_vec();
+        full.extend(glue);
+        full.extend_from_slice(b"and more");
+        assert_eq!(h.finalize(), sha1(&full));
+    }
+
+    #[test]
+    fn secret_prefix() {
+        assert_eq!(secret_prefix_mac(b"key", b"message"), sha1(b"keymessage"));
+    }
+}
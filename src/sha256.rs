@@ -0,0 +1,192 @@
+// SHA-256 (FIPS 180-4).
+// Another Merkle-Damgard hash, resumable the same way as SHA-1 (see
+// `Sha256::resume`), which is why HMAC exists in the first place.
+
+use cryptoutil;
+use hash::{Hash, MdHash};
+use md_padding::{self, Endian};
+
+/// the size of a digest in bytes
+pub const DIGEST_SIZE: usize = 32;
+
+/// the first 32 bits of the fractional parts of the square roots of the first 8
+/// primes
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// the first 32 bits of the fractional parts of the cube roots of the first 64
+/// primes
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// an in-progress SHA-256 computation
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    /// bytes that don't make up a whole block yet
+    buffer: Vec<u8>,
+    /// the number of bytes hashed so far
+    length: u64,
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        Sha256 { state: INITIAL_STATE, buffer: vec![], length: 0 }
+    }
+
+    /// picks up hashing right after the message <digest> is the hash of, which
+    /// was <length> bytes long *including* its padding
+    /// (so <length> must be a whole number of blocks)
+    pub fn resume(digest: &[u8], length: u64) -> Sha256 {
+        assert_eq!(digest.len(), DIGEST_SIZE);
+        assert!(length % md_padding::BLOCK_SIZE as u64 == 0, "resumed length must be a whole number of blocks");
+        let mut state = [0u32; 8];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = md_padding::read_u32(&digest[4 * i..], Endian::Big);
+        }
+        Sha256 { state: state, buffer: vec![], length: length }
+    }
+
+    /// the number of bytes hashed so far
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// hashes <data>
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        let whole = self.buffer.len() / md_padding::BLOCK_SIZE * md_padding::BLOCK_SIZE;
+        let blocks: Vec<u8> = self.buffer.drain(..whole).collect();
+        for block in blocks.chunks(md_padding::BLOCK_SIZE) {
+            self.compress(block);
+        }
+    }
+
+    /// pads the message and returns the digest
+    pub fn finalize(mut self) -> Vec<u8> {
+        let padding = md_padding::md_padding(self.length, Endian::Big);
+        self.update(&padding);
+        self.state.iter().flat_map(|&w| md_padding::u32_bytes(w, Endian::Big)).collect()
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 64];
+        for i in 0..16 {
+            w[i] = md_padding::read_u32(&block[4 * i..], Endian::Big);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (s, v) in self.state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+}
+
+impl Hash for Sha256 {
+    const DIGEST_SIZE: usize = DIGEST_SIZE;
+    const BLOCK_SIZE: usize = md_padding::BLOCK_SIZE;
+
+    fn new() -> Sha256 {
+        Sha256::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha256::update(self, data)
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        Sha256::finalize(self)
+    }
+}
+
+impl MdHash for Sha256 {
+    const ENDIAN: Endian = Endian::Big;
+
+    fn resume(digest: &[u8], length: u64) -> Sha256 {
+        Sha256::resume(digest, length)
+    }
+}
+
+/// returns the SHA-256 digest of <data>
+pub fn sha256(data: &[u8]) -> Vec<u8> {
+    let mut h = Sha256::new();
+    h.update(data);
+    h.finalize()
+}
+
+/// returns the SHA-256 digest of <data> as hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    cryptoutil::bytes_to_hex(&sha256(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FIPS 180 examples
+    #[test]
+    fn known_digests() {
+        assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(sha256_hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+                   "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+        assert_eq!(sha256_hex(&vec![b'a'; 1000000]),
+                   "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for split in 0..data.len() {
+            let mut h = Sha256::new();
+            h.update(&data[..split]);
+            h.update(&data[split..]);
+            assert_eq!(h.finalize(), sha256(&data));
+        }
+    }
+
+    #[test]
+    fn resumes_after_padding() {
+        let message = b"a message";
+        let digest = sha256(message);
+        let glue = md_padding::md_padding(message.len() as u64, Endian::Big);
+        let mut h = Sha256::resume(&digest, (message.len() + glue.len()) as u64);
+        h.update(b"and more");
+        let mut full = message.to_vec();
+        full.extend(glue);
+        full.extend_from_slice(b"and more");
+        assert_eq!(h.finalize(), sha256(&full));
+    }
+}
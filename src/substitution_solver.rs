@@ -0,0 +1,148 @@
+// Breaks monoalphabetic substitution ciphers by simulated annealing over the key.
+// Frequency-rank guessing (`classical::guess_substitution_key`) usually gets the
+// most common handful of letters right and everything else wrong. Starting from
+// there, we keep swapping pairs of letters in the key and keep the swaps that make
+// the plaintext score better under an n-gram model. Occasionally accepting a swap
+// that makes things worse (with a probability that shrinks as we "cool down")
+// keeps the search from getting stuck on a key that's only locally good, and
+// restarting from scratch a few times takes care of the rest.
+
+use classical;
+use ngram::{self, NgramModel};
+use rng::RandomSource;
+
+/// knobs for `anneal_substitution`
+#[derive(Debug, Clone, Copy)]
+pub struct AnnealingConfig {
+    /// how many times to start over from a random key. The first attempt always
+    /// starts from the frequency-rank guess.
+    pub restarts: usize,
+    /// how many swaps to try per attempt
+    pub iterations: usize,
+    /// the temperature each attempt starts at. 0.0 means plain hill climbing,
+    /// i.e. only ever accept swaps that improve the score.
+    pub temperature: f64,
+    /// the temperature is multiplied by this after every swap
+    pub cooling: f64,
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> AnnealingConfig {
+        AnnealingConfig {
+            restarts: 5,
+            iterations: 20000,
+            temperature: 20.0,
+            cooling: 0.9995,
+        }
+    }
+}
+
+/// given a decryption map (ciphertext letter -> plaintext letter), returns the
+/// equivalent encryption key in the format `classical::substitution_encrypt` uses
+fn to_key(decrypt: &[u8; 26]) -> String {
+    let mut key = vec!['a'; 26];
+    for (cipher, &plain) in decrypt.iter().enumerate() {
+        key[plain as usize] = (b'a' + cipher as u8) as char;
+    }
+    key.into_iter().collect()
+}
+
+/// the inverse of `to_key`
+fn from_key(key: &str) -> [u8; 26] {
+    let mut decrypt = [0u8; 26];
+    for (plain, cipher) in key.bytes().enumerate() {
+        decrypt[(cipher.to_ascii_lowercase() - b'a') as usize] = plain as u8;
+    }
+    decrypt
+}
+
+/// scores the plaintext we get by running <ciphertext> (as alphabet positions)
+/// through <decrypt>
+fn score(ciphertext: &[u8], decrypt: &[u8; 26], model: &NgramModel, buf: &mut Vec<u8>) -> f64 {
+    buf.clear();
+    buf.extend(ciphertext.iter().map(|&c| decrypt[c as usize]));
+    model.score_indices(buf)
+}
+
+/// breaks a substitution cipher by simulated annealing, scoring candidate
+/// plaintexts with <model>
+/// with a seeded <rng> (`rng::XorShift`), the same seed always gives the same
+/// answer
+/// returns (key, plaintext), where key is in the format `classical` uses
+pub fn anneal_substitution<R: RandomSource>(ciphertext: &str,
+                                            model: &NgramModel,
+                                            config: &AnnealingConfig,
+                                            rng: &mut R) -> (String, String) {
+    let letters = ngram::to_indices(ciphertext);
+    let mut buf = Vec::with_capacity(letters.len());
+
+    let mut best_key = from_key(&classical::guess_substitution_key(ciphertext));
+    let mut best_score = score(&letters, &best_key, model, &mut buf);
+
+    for attempt in 0..config.restarts + 1 {
+        let mut key = best_key;
+        if attempt > 0 {
+            rng.shuffle(&mut key);
+        }
+        let mut current = score(&letters, &key, model, &mut buf);
+        let mut temperature = config.temperature;
+
+        for _ in 0..config.iterations {
+            let a = rng.gen_range(26);
+            let b = rng.gen_range(26);
+            if a == b {
+                continue;
+            }
+            key.swap(a, b);
+            let candidate = score(&letters, &key, model, &mut buf);
+            let delta = candidate - current;
+            let accept = delta >= 0.0 ||
+                         (temperature > 0.0 && rng.next_f64() < (delta / temperature).exp());
+            if accept {
+                current = candidate;
+                if current > best_score {
+                    best_score = current;
+                    best_key = key;
+                }
+            } else {
+                key.swap(a, b);
+            }
+            temperature *= config.cooling;
+        }
+    }
+
+    let key = to_key(&best_key);
+    let plaintext = classical::substitution_decrypt(ciphertext, &key);
+    (key, plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rng::XorShift;
+
+    #[test]
+    fn key_conversion_round_trips() {
+        let key = "qwertyuiopasdfghjklzxcvbnm";
+        assert_eq!(to_key(&from_key(key)), key);
+    }
+
+    #[test]
+    fn recovers_key() {
+        // the first 2000 characters of the corpus use every letter but 'q', so
+        // that's the only one the key can't be checked for
+        let plaintext = &ngram::ENGLISH_CORPUS[..2000];
+        let key = "phqgiumeaylnofdxjkrcvstzwb";
+        let ciphertext = classical::substitution_encrypt(plaintext, key);
+        let config = AnnealingConfig { restarts: 2, iterations: 5000, ..AnnealingConfig::default() };
+        let (found, decrypted) = anneal_substitution(&ciphertext, &NgramModel::english(3), &config,
+                                                     &mut XorShift::new(28));
+        assert_eq!(decrypted, plaintext);
+        for (plain, (a, b)) in key.chars().zip(found.chars()).enumerate() {
+            let letter = (b'a' + plain as u8) as char;
+            if plaintext.contains(letter) {
+                assert_eq!(a, b, "wrong key letter for {}", letter);
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Recovering a MAC through a timing leak (cryptopals challenges 31 and 32).
+// A server that checks MACs by comparing a byte at a time and bailing out at the
+// first mismatch takes longer the more leading bytes are right. So we can find
+// the MAC one byte at a time: for each position, try all 256 values and keep the
+// one the server spends the longest on. Timing is noisy, so each guess is timed
+// several times and we go by the fastest run: noise (other processes, interrupts)
+// only ever makes a run slower, so the fastest is the closest to the truth.
+
+use std::time::{Duration, Instant};
+use cryptoutil;
+use hash::Hash;
+use hmac;
+
+/// waits for <delay> by spinning, rather than sleeping
+/// sleeping overshoots by more than the short delays the attack gets tested
+/// with, which would drown out the leak
+fn busy_wait(delay: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < delay {}
+}
+
+/// from challenge 4-31
+/// compares two byte strings the wrong way: one byte at a time, waiting <delay>
+/// after each byte that matches, and returning as soon as one doesn't
+pub fn insecure_compare(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return false;
+        }
+        busy_wait(delay);
+    }
+    true
+}
+
+/// a stand-in for a web service that checks HMAC signatures on files, with
+/// `insecure_compare` unless told otherwise
+pub struct HmacVerifier<H: Hash> {
+    key: Vec<u8>,
+    /// None means compare with `cryptoutil::constant_time_eq`
+    delay: Option<Duration>,
+    _hash: ::std::marker::PhantomData<H>,
+}
+
+impl<H: Hash> HmacVerifier<H> {
+    /// a service with MAC key <key>, which waits <delay> per matching byte (the
+    /// challenges use 50ms and then 5ms, but far less still works)
+    pub fn new(key: &[u8], delay: Duration) -> HmacVerifier<H> {
+        HmacVerifier { key: key.to_vec(), delay: Some(delay), _hash: ::std::marker::PhantomData }
+    }
+
+    /// a service with MAC key <key> that compares signatures in constant time
+    pub fn secure(key: &[u8]) -> HmacVerifier<H> {
+        HmacVerifier { key: key.to_vec(), delay: None, _hash: ::std::marker::PhantomData }
+    }
+
+    /// the signature the service expects for <file>, so we can check our answer
+    pub fn expected(&self, file: &[u8]) -> Vec<u8> {
+        hmac::hmac::<H>(&self.key, file)
+    }
+
+    /// returns true if <signature> is the right MAC for <file>
+    pub fn verify(&self, file: &[u8], signature: &[u8]) -> bool {
+        match self.delay {
+            Some(delay) => insecure_compare(&self.expected(file), signature, delay),
+            None => cryptoutil::constant_time_eq(&self.expected(file), signature),
+        }
+    }
+}
+
+/// knobs for `recover_mac`
+#[derive(Debug, Clone, Copy)]
+pub struct TimingAttackConfig {
+    /// how many times to time each guess. More is slower, but less likely to be
+    /// thrown off by noise.
+    pub samples: usize,
+    /// how many times to re-time a position where no guess stands out from the
+    /// rest, before deciding that an earlier byte must be wrong and backing up
+    pub retries: usize,
+    /// how many times to back up in total before giving up
+    pub max_backtracks: usize,
+}
+
+impl Default for TimingAttackConfig {
+    fn default() -> TimingAttackConfig {
+        TimingAttackConfig { samples: 5, retries: 2, max_backtracks: 10 }
+    }
+}
+
+/// returns how long <verify> takes on <guess>, as the fastest of <samples> runs
+fn time_guess<F>(verify: &mut F, guess: &[u8], samples: usize) -> Duration
+    where F: FnMut(&[u8]) -> bool {
+    (0..samples).map(|_| {
+        let start = Instant::now();
+        verify(guess);
+        start.elapsed()
+    }).min().unwrap()
+}
+
+/// times every value for mac[position], and returns the one that takes clearly
+/// longer than the rest, if any does
+fn slowest_byte<F>(verify: &mut F, mac: &mut [u8], position: usize, config: &TimingAttackConfig) -> Option<u8>
+    where F: FnMut(&[u8]) -> bool {
+    for _ in 0..config.retries + 1 {
+        let mut times: Vec<(Duration, u8)> = (0..256).map(|b| {
+            mac[position] = b as u8;
+            (time_guess(verify, mac, config.samples), b as u8)
+        }).collect();
+        times.sort();
+        // the right byte should take a whole extra delay longer than the rest,
+        // which all take about the same time. If the slowest is barely ahead of
+        // the runner up, it's just noise (or an earlier byte is wrong, so every
+        // guess gets rejected equally fast).
+        let ((best, byte), runner_up, median) = (times[255], times[254].0, times[128].0);
+        if best - runner_up > (runner_up - median) * 2 {
+            return Some(byte);
+        }
+    }
+    None
+}
+
+/// recovers the <mac_len>-byte MAC that <verify> accepts, by timing how long it
+/// takes to reject guesses
+/// <progress> is called with the MAC so far whenever it changes
+/// returns None if the timing is too noisy to get anywhere
+pub fn recover_mac<F, P>(mac_len: usize, config: &TimingAttackConfig, mut verify: F, mut progress: P) -> Option<Vec<u8>>
+    where F: FnMut(&[u8]) -> bool, P: FnMut(&[u8]) {
+    assert!(config.samples > 0 && mac_len > 0);
+    let mut mac = vec![0u8; mac_len];
+    let mut position = 0;
+    let mut backtracks = 0;
+    loop {
+        let found = if position == mac_len - 1 {
+            // no need to time the last byte: exactly one value verifies
+            (0..256).map(|b| b as u8).find(|&b| {
+                mac[position] = b;
+                verify(&mac)
+            })
+        } else {
+            slowest_byte(&mut verify, &mut mac, position, config)
+        };
+        match found {
+            Some(byte) => {
+                mac[position] = byte;
+                progress(&mac[..position + 1]);
+                if position == mac_len - 1 {
+                    return Some(mac);
+                }
+                position += 1;
+            }
+            None => {
+                if backtracks == config.max_backtracks {
+                    return None;
+                }
+                backtracks += 1;
+                position = position.saturating_sub(1);
+                progress(&mac[..position]);
+            }
+        }
+    }
+}
+
+/// the |t| above which `TimingComparison::leaks` calls a difference real
+/// (the TVLA convention for leakage assessment; dudect is more conservative,
+/// using 10, and 500 for a definite leak)
+pub const LEAK_THRESHOLD: f64 = 4.5;
+
+/// timings of the same operation on two classes of input
+#[derive(Debug, Clone)]
+pub struct TimingComparison {
+    /// mean and variance (in nanoseconds) of each class
+    pub mean: (f64, f64),
+    pub variance: (f64, f64),
+    /// welch's t statistic for the difference in means
+    pub t: f64,
+}
+
+impl TimingComparison {
+    /// returns true if the two classes take measurably different times
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > LEAK_THRESHOLD
+    }
+}
+
+/// returns the mean and (sample) variance of <xs>
+fn mean_variance(xs: &[f64]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    let variance = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// times <f> on <class_a> and <class_b>, <samples> times each, and tests whether
+/// they take different amounts of time
+/// the two classes are timed alternately so that anything else going on (the CPU
+/// clocking up, another process waking up) hits both of them alike
+pub fn compare_timing<F>(class_a: &[u8], class_b: &[u8], samples: usize, mut f: F) -> TimingComparison
+    where F: FnMut(&[u8]) -> bool {
+    assert!(samples >= 2);
+    let mut time = |input: &[u8]| {
+        let start = Instant::now();
+        ::std::hint::black_box(f(input));
+        let elapsed = start.elapsed();
+        elapsed.as_secs() as f64 * 1e9 + elapsed.subsec_nanos() as f64
+    };
+    let (mut a, mut b) = (Vec::with_capacity(samples), Vec::with_capacity(samples));
+    for _ in 0..samples {
+        a.push(time(class_a));
+        b.push(time(class_b));
+    }
+    let ((mean_a, var_a), (mean_b, var_b)) = (mean_variance(&a), mean_variance(&b));
+    let t = (mean_a - mean_b) / (var_a / samples as f64 + var_b / samples as f64).sqrt();
+    TimingComparison { mean: (mean_a, mean_b), variance: (var_a, var_b), t: t }
+}
+
+/// checks whether comparing against <secret> with <compare> leaks how many
+/// leading bytes were right, by timing a guess that's wrong from the first byte
+/// against one that's only wrong in the last byte
+pub fn measure_compare<F>(secret: &[u8], samples: usize, mut compare: F) -> TimingComparison
+    where F: FnMut(&[u8], &[u8]) -> bool {
+    assert!(!secret.is_empty());
+    let mut early = secret.to_vec();
+    early[0] ^= 1;
+    let mut late = secret.to_vec();
+    let last = late.len() - 1;
+    late[last] ^= 1;
+    compare_timing(&early, &late, samples, |guess| compare(secret, guess))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::Sha1;
+
+    #[test]
+    fn compares_a_byte_at_a_time() {
+        let delay = Duration::from_millis(1);
+        assert!(insecure_compare(b"", b"", delay));
+        assert!(insecure_compare(b"abc", b"abc", delay));
+        assert!(!insecure_compare(b"abc", b"xbc", delay));
+        assert!(!insecure_compare(b"abc", b"abx", delay));
+        assert!(!insecure_compare(b"abc", b"ab", delay));
+        assert!(!insecure_compare(b"ab", b"abc", delay));
+        // every matching byte costs a delay, so a late mismatch is slow
+        let start = Instant::now();
+        insecure_compare(b"abcd", b"abcx", delay);
+        assert!(start.elapsed() >= delay * 3);
+    }
+
+    #[test]
+    fn recovers_a_mac() {
+        let server = HmacVerifier::<Sha1>::new(b"a key", Duration::from_micros(200));
+        let file = b"foo";
+        let expected = server.expected(file);
+        assert!(server.verify(file, &expected));
+        assert!(!server.verify(file, &expected[1..]));
+        // a whole SHA-1 MAC takes a while even with a short delay, so only go
+        // after its first few bytes, and fill in the rest from the real one
+        let len = 3;
+        let mut progress = vec![];
+        let mac = recover_mac(len, &TimingAttackConfig::default(), |guess| {
+            let mut signature = guess.to_vec();
+            signature.extend_from_slice(&expected[len..]);
+            server.verify(file, &signature)
+        }, |so_far| progress.push(so_far.to_vec()));
+        assert_eq!(mac, Some(expected[..len].to_vec()));
+        assert_eq!(progress.last().map(|p| &p[..]), Some(&expected[..len]));
+    }
+
+    #[test]
+    fn spots_a_leaky_compare() {
+        let secret: Vec<u8> = (0..32).collect();
+        let insecure = measure_compare(&secret, 100, |a, b| insecure_compare(a, b, Duration::from_micros(20)));
+        assert!(insecure.leaks());
+        // the guess that's only wrong in its last byte is the slow one
+        assert!(insecure.mean.1 > insecure.mean.0 && insecure.t < 0.0);
+        // timing is noisy enough that a constant-time compare can look like it
+        // leaks once in a while, but a real leak shows up every time
+        assert!((0..3).any(|_| {
+            !measure_compare(&secret, 5000, |a, b| cryptoutil::constant_time_eq(a, b)).leaks()
+        }));
+    }
+
+    #[test]
+    fn welch_t() {
+        let slow = compare_timing(b"slow", b"fast", 200, |input| {
+            if input == b"slow" {
+                busy_wait(Duration::from_micros(50));
+            }
+            true
+        });
+        assert!(slow.leaks() && slow.t > 0.0);
+        assert!(slow.mean.0 >= 50000.0 && slow.mean.1 < slow.mean.0);
+    }
+}
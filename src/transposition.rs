@@ -0,0 +1,242 @@
+// Transposition ciphers: columnar and rail fence.
+// These don't change any of the bytes, only their order, so frequency analysis
+// is useless against them (the letter frequencies of the ciphertext are exactly
+// those of the plaintext). Instead, the breakers try keys and score the results
+// with an n-gram model, which does care about order.
+
+use std::ops::Range;
+use cryptoutil;
+use ngram::NgramModel;
+
+/// the widest columnar key `break_columnar` will try. Every permutation of the
+/// columns gets tried, and 8! is already 40320.
+pub const MAX_BRUTE_FORCE_WIDTH: usize = 8;
+
+/// given a keyword, returns the column order it describes: the column under the
+/// alphabetically first letter is read out first, and so on (ties are broken
+/// left to right)
+/// e.g. "ZEBRAS" gives [4, 2, 1, 3, 5, 0]
+/// panics if the keyword isn't ascii, since every byte gets a column
+pub fn keyword_order(keyword: &str) -> Vec<usize> {
+    assert!(keyword.is_ascii(), "keyword must be ascii: {}", keyword);
+    let mut order: Vec<usize> = (0..keyword.len()).collect();
+    let bytes: Vec<u8> = keyword.bytes().map(|b| b.to_ascii_lowercase()).collect();
+    order.sort_by_key(|&i| bytes[i]);
+    order
+}
+
+/// encrypts with a columnar transposition: the text is written out in rows as
+/// wide as the key, and the columns are read out in the order given by <order>
+/// (order[0] is the first column read, and so on)
+/// the last row is allowed to be short, so no padding is needed
+pub fn columnar_encrypt(text: &[u8], order: &[usize]) -> Vec<u8> {
+    let columns = cryptoutil::transpose(text, order.len());
+    order.iter().flat_map(|&i| columns[i].iter().cloned()).collect()
+}
+
+/// undoes `columnar_encrypt`
+pub fn columnar_decrypt(ciphertext: &[u8], order: &[usize]) -> Vec<u8> {
+    let width = order.len();
+    assert!(width > 0);
+    // with a short last row, the columns on the left are one byte longer
+    let (rows, long_columns) = (ciphertext.len() / width, ciphertext.len() % width);
+    let mut columns = vec![vec![]; width];
+    let mut offset = 0;
+    for &i in order {
+        let len = rows + if i < long_columns { 1 } else { 0 };
+        columns[i] = ciphertext[offset..offset+len].to_vec();
+        offset += len;
+    }
+    cryptoutil::untranspose(&columns)
+}
+
+/// returns the rail each position of a <len>-byte text lands on
+fn rail_pattern(len: usize, rails: usize) -> Vec<usize> {
+    assert!(rails >= 2, "a rail fence needs at least two rails");
+    let cycle = 2 * (rails - 1);
+    (0..len).map(|i| {
+        let r = i % cycle;
+        if r < rails { r } else { cycle - r }
+    }).collect()
+}
+
+/// returns the positions of the plaintext in the order they appear in the
+/// ciphertext
+fn rail_fence_permutation(len: usize, rails: usize) -> Vec<usize> {
+    let pattern = rail_pattern(len, rails);
+    let mut positions: Vec<usize> = (0..len).collect();
+    // sort_by_key is stable, so each rail keeps its left-to-right order
+    positions.sort_by_key(|&i| pattern[i]);
+    positions
+}
+
+/// encrypts with a rail fence cipher: the text is written in a zigzag over
+/// <rails> rows, and then read out row by row
+pub fn rail_fence_encrypt(text: &[u8], rails: usize) -> Vec<u8> {
+    rail_fence_permutation(text.len(), rails).iter().map(|&i| text[i]).collect()
+}
+
+/// undoes `rail_fence_encrypt`
+pub fn rail_fence_decrypt(ciphertext: &[u8], rails: usize) -> Vec<u8> {
+    let mut text = vec![0; ciphertext.len()];
+    for (&i, &b) in rail_fence_permutation(ciphertext.len(), rails).iter().zip(ciphertext) {
+        text[i] = b;
+    }
+    text
+}
+
+/// scores a candidate plaintext with <model>
+fn score(text: &[u8], model: &NgramModel) -> f64 {
+    model.score(&String::from_utf8_lossy(text))
+}
+
+/// tries every number of rails in <rails> and returns (rails, score) for each,
+/// best first
+/// panics if <rails> is empty or starts below 2 (a rail fence needs at least two
+/// rails)
+pub fn rank_rail_fence(ciphertext: &[u8], rails: Range<usize>, model: &NgramModel) -> Vec<(usize, f64)> {
+    assert!(rails.start < rails.end, "no rail counts to try");
+    assert!(rails.start >= 2, "a rail fence needs at least two rails, so the range can't start at {}",
+            rails.start);
+    let mut v: Vec<(usize, f64)> = rails
+        .map(|r| (r, score(&rail_fence_decrypt(ciphertext, r), model)))
+        .collect();
+    v.sort_by(|a, b| b.1.total_cmp(&a.1));
+    v
+}
+
+/// breaks a rail fence cipher, trying every number of rails in <rails>
+/// returns (rails, plaintext)
+/// panics on the same ranges as `rank_rail_fence`
+pub fn break_rail_fence(ciphertext: &[u8], rails: Range<usize>, model: &NgramModel) -> (usize, Vec<u8>) {
+    let (best, _) = rank_rail_fence(ciphertext, rails, model)[0];
+    (best, rail_fence_decrypt(ciphertext, best))
+}
+
+/// calls <f> with every permutation of 0..n (heap's algorithm)
+fn for_each_permutation<F>(n: usize, mut f: F) where F: FnMut(&[usize]) {
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut c = vec![0; n];
+    f(&perm);
+    let mut i = 0;
+    while i < n {
+        if c[i] < i {
+            if i % 2 == 0 { perm.swap(0, i); } else { perm.swap(c[i], i); }
+            f(&perm);
+            c[i] += 1;
+            i = 0;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// finds the best column order for every key width in <widths>
+/// returns (order, score) for each width, best first. The width of the key is
+/// the length of the order.
+/// panics if <widths> is empty, starts at 0, or goes above
+/// `MAX_BRUTE_FORCE_WIDTH`
+pub fn rank_columnar_widths(ciphertext: &[u8],
+                            widths: Range<usize>,
+                            model: &NgramModel) -> Vec<(Vec<usize>, f64)> {
+    assert!(widths.start < widths.end, "no key widths to try");
+    assert!(widths.start >= 1, "a columnar key has to be at least one column wide");
+    let mut v = vec![];
+    for width in widths {
+        assert!(width <= MAX_BRUTE_FORCE_WIDTH,
+                "can't brute force a columnar key of width {}", width);
+        let mut best = (vec![], ::std::f64::NEG_INFINITY);
+        for_each_permutation(width, |order| {
+            let s = score(&columnar_decrypt(ciphertext, order), model);
+            if s > best.1 {
+                best = (order.to_vec(), s);
+            }
+        });
+        v.push(best);
+    }
+    v.sort_by(|a, b| b.1.total_cmp(&a.1));
+    v
+}
+
+/// breaks a columnar transposition, trying every key width in <widths>
+/// returns (order, plaintext)
+/// panics on the same ranges as `rank_columnar_widths`
+pub fn break_columnar(ciphertext: &[u8], widths: Range<usize>, model: &NgramModel) -> (Vec<usize>, Vec<u8>) {
+    let ranked = rank_columnar_widths(ciphertext, widths, model);
+    let order = ranked.into_iter().next().unwrap().0;
+    let plaintext = columnar_decrypt(ciphertext, &order);
+    (order, plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ngram::ENGLISH_CORPUS;
+
+    const TEXT: &[u8] = b"WEAREDISCOVEREDFLEEATONCE";
+
+    #[test]
+    fn keyword_orders() {
+        assert_eq!(keyword_order("ZEBRAS"), vec![4, 2, 1, 3, 5, 0]);
+        assert_eq!(keyword_order("zebras"), keyword_order("ZEBRAS"));
+        // repeated letters are read left to right
+        assert_eq!(keyword_order("ABA"), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn columnar_round_trip() {
+        let order = keyword_order("ZEBRAS");
+        assert_eq!(columnar_encrypt(TEXT, &order), b"EVLNACDTESEAROFODEECWIREE".to_vec());
+        for len in 0..TEXT.len() + 1 {
+            assert_eq!(columnar_decrypt(&columnar_encrypt(&TEXT[..len], &order), &order), &TEXT[..len]);
+        }
+    }
+
+    #[test]
+    fn rail_fence_round_trip() {
+        assert_eq!(rail_fence_encrypt(TEXT, 3), b"WECRLTEERDSOEEFEAOCAIVDEN".to_vec());
+        for rails in 2..8 {
+            assert_eq!(rail_fence_decrypt(&rail_fence_encrypt(TEXT, rails), rails), TEXT);
+        }
+    }
+
+    #[test]
+    fn breaks_rail_fence() {
+        let model = NgramModel::english(3);
+        let text = &ENGLISH_CORPUS.as_bytes()[..500];
+        assert_eq!(break_rail_fence(&rail_fence_encrypt(text, 5), 2..20, &model), (5, text.to_vec()));
+    }
+
+    #[test]
+    fn breaks_columnar() {
+        let model = NgramModel::english(3);
+        let text = &ENGLISH_CORPUS.as_bytes()[..500];
+        let order = keyword_order("ZEBRAS");
+        assert_eq!(break_columnar(&columnar_encrypt(text, &order), 2..7, &model), (order, text.to_vec()));
+    }
+
+    #[test]
+    #[should_panic(expected = "keyword must be ascii")]
+    fn rejects_non_ascii_keywords() {
+        keyword_order("ZÈBRAS");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least two rails")]
+    fn rejects_one_rail() {
+        break_rail_fence(TEXT, 1..5, &NgramModel::english(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "no rail counts")]
+    fn rejects_no_rails() {
+        break_rail_fence(TEXT, 3..3, &NgramModel::english(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one column")]
+    fn rejects_zero_width() {
+        break_columnar(TEXT, 0..4, &NgramModel::english(3));
+    }
+}